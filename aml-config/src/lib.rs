@@ -1,22 +1,110 @@
+use std::collections::HashMap;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
+use toml::Spanned;
 
 #[derive(Debug)]
 pub struct Config {
     pub templates_dir: PathBuf,
     pub root_template: String,
+    pub lints: LintConfig,
+    /// The lowest contrast ratio between the foreground and the background of text, from 1 to 21.
+    pub min_contrast_ratio: f64,
+    /// The colours the terminals the application targets can show, `None` for any colour.
+    pub color_depth: Option<ColorDepth>,
+    /// The settings that are not valid, which are left at their default.
+    pub errors: Vec<ConfigError>,
+}
+
+/// A setting of `aml_ls.toml` that is not valid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub message: String,
+    /// Where the value is in `aml_ls.toml`.
+    pub span: Range<usize>,
+}
+
+/// How many colours a terminal can show, configured as `color_depth = 16` or `color_depth = 256`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    /// The 16 ANSI colours.
+    Ansi16,
+    /// The 256 colours of xterm.
+    Xterm256,
+}
+
+/// The contrast WCAG requires for normal text.
+pub const DEFAULT_MIN_CONTRAST_RATIO: f64 = 4.5;
+
+/// The level of lints, configured in the `[lints]` table of `aml_ls.toml` by lint name, e.g.
+/// `unused-local = "allow"`.
+#[derive(Debug, Default)]
+pub struct LintConfig {
+    pub levels: HashMap<String, LintLevel>,
+    /// Where each lint is named in `aml_ls.toml`, to report the names that are not lints.
+    pub spans: HashMap<String, Range<usize>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
 }
 
 impl From<RawConfig> for Config {
     fn from(raw_config: RawConfig) -> Self {
+        let mut errors = vec![];
+        let color_depth =
+            raw_config
+                .color_depth
+                .and_then(|color_depth| match color_depth.get_ref() {
+                    toml::Value::Integer(16) => Some(ColorDepth::Ansi16),
+                    toml::Value::Integer(256) => Some(ColorDepth::Xterm256),
+                    value => {
+                        errors.push(ConfigError {
+                            message: format!("invalid colour depth {value}, expected 16 or 256"),
+                            span: color_depth.span(),
+                        });
+                        None
+                    }
+                });
+
         Self {
             templates_dir: raw_config
                 .templates_dir
                 .unwrap_or("templates".into())
                 .into(),
             root_template: raw_config.root_template.unwrap_or("index.aml".into()),
+            lints: raw_config.lints.map(Into::into).unwrap_or_default(),
+            min_contrast_ratio: raw_config
+                .min_contrast_ratio
+                .unwrap_or(DEFAULT_MIN_CONTRAST_RATIO),
+            color_depth,
+            errors,
+        }
+    }
+}
+
+impl From<HashMap<Spanned<String>, RawLintLevel>> for LintConfig {
+    fn from(raw_lints: HashMap<Spanned<String>, RawLintLevel>) -> Self {
+        let mut config = Self::default();
+        for (name, level) in raw_lints {
+            let level = match level {
+                RawLintLevel::Level(level) => level,
+                RawLintLevel::Enabled(true) => LintLevel::Warn,
+                RawLintLevel::Enabled(false) => LintLevel::Allow,
+            };
+            let span = name.span();
+            let name = name.into_inner().replace('_', "-");
+            config.spans.insert(name.clone(), span);
+            config.levels.insert(name, level);
         }
+
+        config
     }
 }
 
@@ -24,6 +112,17 @@ impl From<RawConfig> for Config {
 struct RawConfig {
     templates_dir: Option<String>,
     root_template: Option<String>,
+    min_contrast_ratio: Option<f64>,
+    color_depth: Option<Spanned<toml::Value>>,
+    lints: Option<HashMap<Spanned<String>, RawLintLevel>>,
+}
+
+/// Lints were first turned on and off, `shadowing = true` still means `shadowing = "warn"`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawLintLevel {
+    Enabled(bool),
+    Level(LintLevel),
 }
 
 impl Default for Config {
@@ -31,12 +130,21 @@ impl Default for Config {
         Self {
             templates_dir: "templates".into(),
             root_template: "index.aml".into(),
+            lints: LintConfig::default(),
+            min_contrast_ratio: DEFAULT_MIN_CONTRAST_RATIO,
+            color_depth: None,
+            errors: vec![],
         }
     }
 }
 
+/// Where the configuration of the project at `root_dir` is.
+pub fn config_path<P: AsRef<Path>>(root_dir: P) -> PathBuf {
+    root_dir.as_ref().join("aml_ls.toml")
+}
+
 fn load_project_config<P: AsRef<Path>>(root_dir: P) -> Option<Config> {
-    let config_path = root_dir.as_ref().join("aml_ls.toml");
+    let config_path = config_path(root_dir);
     if !config_path.exists() {
         return None;
     };
@@ -57,3 +165,43 @@ pub fn load_config<P: AsRef<Path>>(root_dir: Option<P>) -> Config {
         None => Config::default(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_color_depth() {
+        let config = convert_from_toml("color_depth = 256").unwrap();
+        assert_eq!(config.color_depth, Some(ColorDepth::Xterm256));
+        assert!(config.errors.is_empty());
+
+        // the rest of the configuration is still read
+        let config = convert_from_toml("root_template = \"main.aml\"\ncolor_depth = \"24bit\"");
+        let config = config.unwrap();
+        assert_eq!(config.root_template, "main.aml");
+        assert_eq!(config.color_depth, None);
+        assert_eq!(
+            config.errors,
+            vec![ConfigError {
+                message: "invalid colour depth \"24bit\", expected 16 or 256".into(),
+                span: 41..48,
+            }]
+        );
+
+        let config = convert_from_toml("color_depth = 8").unwrap();
+        assert_eq!(
+            config.errors[0].message,
+            "invalid colour depth 8, expected 16 or 256"
+        );
+    }
+
+    #[test]
+    fn test_lints() {
+        let config = convert_from_toml("[lints]\nunused_local = \"allow\"\nshadowing = true");
+        let lints = config.unwrap().lints;
+        assert_eq!(lints.levels["unused-local"], LintLevel::Allow);
+        assert_eq!(lints.levels["shadowing"], LintLevel::Warn);
+        assert_eq!(lints.spans["unused-local"], 8..20);
+    }
+}
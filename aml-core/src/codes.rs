@@ -0,0 +1,247 @@
+//! The stable codes of every diagnostic. A code never changes meaning once released, and is
+//! explained at length by `aml_ls explain <code>`.
+//!
+//! Codes are grouped by where the diagnostic comes from: `AML00xx` for semantic errors,
+//! `AML01xx` for syntax errors and `AML02xx` for lints, which can be allowed or denied by name.
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Code {
+    /// The code itself, e.g. `AML0001`.
+    pub code: &'static str,
+    /// A short name for the code, which is also the name of the lint for lints.
+    pub name: &'static str,
+}
+
+pub static UNDEFINED_IDENTIFIER: Code = Code {
+    code: "AML0001",
+    name: "undefined-identifier",
+};
+
+pub static UNKNOWN_FIELD: Code = Code {
+    code: "AML0002",
+    name: "unknown-field",
+};
+
+pub static UNKNOWN_FUNCTION: Code = Code {
+    code: "AML0003",
+    name: "unknown-function",
+};
+
+pub static ARGUMENT_COUNT: Code = Code {
+    code: "AML0004",
+    name: "argument-count",
+};
+
+pub static ARGUMENT_TYPE: Code = Code {
+    code: "AML0005",
+    name: "argument-type",
+};
+
+/// An expression that always fails to evaluate, e.g. a division by zero.
+pub static INVALID_EXPRESSION: Code = Code {
+    code: "AML0006",
+    name: "invalid-expression",
+};
+
+pub static MISSING_TEMPLATE: Code = Code {
+    code: "AML0007",
+    name: "missing-template",
+};
+
+pub static CYCLIC_COMPONENT: Code = Code {
+    code: "AML0008",
+    name: "cyclic-component",
+};
+
+pub static SPAN_OUTSIDE_TEXT: Code = Code {
+    code: "AML0009",
+    name: "span-outside-text",
+};
+
+pub static INVALID_TEXT_VALUE: Code = Code {
+    code: "AML0010",
+    name: "invalid-text-value",
+};
+
+pub static TOO_MANY_CHILDREN: Code = Code {
+    code: "AML0011",
+    name: "too-many-children",
+};
+
+pub static INVALID_STATE_SCHEMA: Code = Code {
+    code: "AML0012",
+    name: "invalid-state-schema",
+};
+
+pub static UNKNOWN_LINT: Code = Code {
+    code: "AML0013",
+    name: "unknown-lint",
+};
+
+pub static INVALID_CONFIG: Code = Code {
+    code: "AML0014",
+    name: "invalid-config",
+};
+
+pub static UNTERMINATED_STRING: Code = Code {
+    code: "AML0101",
+    name: "unterminated-string",
+};
+
+pub static INVALID_HEX: Code = Code {
+    code: "AML0102",
+    name: "invalid-hex",
+};
+
+pub static UNEXPECTED_TOKEN: Code = Code {
+    code: "AML0103",
+    name: "unexpected-token",
+};
+
+pub static CONFLICTING_GLOBALS: Code = Code {
+    code: "AML0201",
+    name: "conflicting-globals",
+};
+
+pub static SHADOWING: Code = Code {
+    code: "AML0202",
+    name: "shadowing",
+};
+
+pub static UNUSED_LOCAL: Code = Code {
+    code: "AML0203",
+    name: "unused-local",
+};
+
+pub static UNKNOWN_ATTRIBUTE: Code = Code {
+    code: "AML0204",
+    name: "unknown-attribute",
+};
+
+pub static MISSING_ATTRIBUTES: Code = Code {
+    code: "AML0205",
+    name: "missing-attributes",
+};
+
+pub static DUPLICATE_ATTRIBUTE: Code = Code {
+    code: "AML0206",
+    name: "duplicate-attribute",
+};
+
+pub static EMPTY_TEXT: Code = Code {
+    code: "AML0207",
+    name: "empty-text",
+};
+
+pub static INVALID_TEXT_CHILD: Code = Code {
+    code: "AML0208",
+    name: "invalid-text-child",
+};
+
+pub static MIXED_LIST: Code = Code {
+    code: "AML0209",
+    name: "mixed-list",
+};
+
+pub static LOW_CONTRAST: Code = Code {
+    code: "AML0210",
+    name: "low-contrast",
+};
+
+pub static COLOR_DEPTH: Code = Code {
+    code: "AML0211",
+    name: "color-depth",
+};
+
+pub static UNKNOWN_COLOR: Code = Code {
+    code: "AML0212",
+    name: "unknown-color",
+};
+
+pub static UNUSED_TEMPLATE: Code = Code {
+    code: "AML0213",
+    name: "unused-template",
+};
+
+pub static UNUSED_GLOBAL: Code = Code {
+    code: "AML0214",
+    name: "unused-global",
+};
+
+pub static UNQUERIED_ELEMENT: Code = Code {
+    code: "AML0215",
+    name: "unqueried-element",
+};
+
+pub static UNMATCHED_QUERY: Code = Code {
+    code: "AML0216",
+    name: "unmatched-query",
+};
+
+/// Every code, in order.
+pub static CODES: &[&Code] = &[
+    &UNDEFINED_IDENTIFIER,
+    &UNKNOWN_FIELD,
+    &UNKNOWN_FUNCTION,
+    &ARGUMENT_COUNT,
+    &ARGUMENT_TYPE,
+    &INVALID_EXPRESSION,
+    &MISSING_TEMPLATE,
+    &CYCLIC_COMPONENT,
+    &SPAN_OUTSIDE_TEXT,
+    &INVALID_TEXT_VALUE,
+    &TOO_MANY_CHILDREN,
+    &INVALID_STATE_SCHEMA,
+    &UNKNOWN_LINT,
+    &INVALID_CONFIG,
+    &UNTERMINATED_STRING,
+    &INVALID_HEX,
+    &UNEXPECTED_TOKEN,
+    &CONFLICTING_GLOBALS,
+    &SHADOWING,
+    &UNUSED_LOCAL,
+    &UNKNOWN_ATTRIBUTE,
+    &MISSING_ATTRIBUTES,
+    &DUPLICATE_ATTRIBUTE,
+    &EMPTY_TEXT,
+    &INVALID_TEXT_CHILD,
+    &MIXED_LIST,
+    &LOW_CONTRAST,
+    &COLOR_DEPTH,
+    &UNKNOWN_COLOR,
+    &UNUSED_TEMPLATE,
+    &UNUSED_GLOBAL,
+    &UNQUERIED_ELEMENT,
+    &UNMATCHED_QUERY,
+];
+
+/// Looks a code up by itself, in any case, or by its name: `AML0001`, `aml0001` and
+/// `undefined-identifier` are the same code.
+pub fn find(code: &str) -> Option<&'static Code> {
+    CODES
+        .iter()
+        .copied()
+        .find(|known| known.code.eq_ignore_ascii_case(code) || known.name == code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_are_unique() {
+        for (index, code) in CODES.iter().enumerate() {
+            assert!(
+                CODES[index + 1..]
+                    .iter()
+                    .all(|other| other.code != code.code && other.name != code.name),
+                "{} is not unique",
+                code.code
+            );
+        }
+
+        assert_eq!(find("aml0001"), Some(&UNDEFINED_IDENTIFIER));
+        assert_eq!(find("unused-global"), Some(&UNUSED_GLOBAL));
+        assert_eq!(find("AML9999"), None);
+    }
+}
@@ -1,6 +1,8 @@
+pub mod codes;
 pub mod diagnostics;
 pub mod location;
 pub mod workspace;
 
+pub use codes::Code;
 pub use diagnostics::{Diagnostic, Severity};
 pub use location::Location;
@@ -1,3 +1,5 @@
+use std::path::Path;
+
 #[derive(Debug)]
 pub struct Docs {
     pub text: &'static str,
@@ -48,3 +50,86 @@ impl Docs {
 }
 
 pub fn lib() {}
+
+/// The long-form explanation of a diagnostic code, e.g. `AML0001`, with examples.
+pub fn explanation(code: &str) -> Option<&'static str> {
+    let explanation = match code {
+        "AML0001" => include_str!("../../docs/diagnostics/AML0001.md"),
+        "AML0002" => include_str!("../../docs/diagnostics/AML0002.md"),
+        "AML0003" => include_str!("../../docs/diagnostics/AML0003.md"),
+        "AML0004" => include_str!("../../docs/diagnostics/AML0004.md"),
+        "AML0005" => include_str!("../../docs/diagnostics/AML0005.md"),
+        "AML0006" => include_str!("../../docs/diagnostics/AML0006.md"),
+        "AML0007" => include_str!("../../docs/diagnostics/AML0007.md"),
+        "AML0008" => include_str!("../../docs/diagnostics/AML0008.md"),
+        "AML0009" => include_str!("../../docs/diagnostics/AML0009.md"),
+        "AML0010" => include_str!("../../docs/diagnostics/AML0010.md"),
+        "AML0011" => include_str!("../../docs/diagnostics/AML0011.md"),
+        "AML0012" => include_str!("../../docs/diagnostics/AML0012.md"),
+        "AML0013" => include_str!("../../docs/diagnostics/AML0013.md"),
+        "AML0014" => include_str!("../../docs/diagnostics/AML0014.md"),
+        "AML0101" => include_str!("../../docs/diagnostics/AML0101.md"),
+        "AML0102" => include_str!("../../docs/diagnostics/AML0102.md"),
+        "AML0103" => include_str!("../../docs/diagnostics/AML0103.md"),
+        "AML0201" => include_str!("../../docs/diagnostics/AML0201.md"),
+        "AML0202" => include_str!("../../docs/diagnostics/AML0202.md"),
+        "AML0203" => include_str!("../../docs/diagnostics/AML0203.md"),
+        "AML0204" => include_str!("../../docs/diagnostics/AML0204.md"),
+        "AML0205" => include_str!("../../docs/diagnostics/AML0205.md"),
+        "AML0206" => include_str!("../../docs/diagnostics/AML0206.md"),
+        "AML0207" => include_str!("../../docs/diagnostics/AML0207.md"),
+        "AML0208" => include_str!("../../docs/diagnostics/AML0208.md"),
+        "AML0209" => include_str!("../../docs/diagnostics/AML0209.md"),
+        "AML0210" => include_str!("../../docs/diagnostics/AML0210.md"),
+        "AML0211" => include_str!("../../docs/diagnostics/AML0211.md"),
+        "AML0212" => include_str!("../../docs/diagnostics/AML0212.md"),
+        "AML0213" => include_str!("../../docs/diagnostics/AML0213.md"),
+        "AML0214" => include_str!("../../docs/diagnostics/AML0214.md"),
+        "AML0215" => include_str!("../../docs/diagnostics/AML0215.md"),
+        "AML0216" => include_str!("../../docs/diagnostics/AML0216.md"),
+        _ => return None,
+    };
+
+    Some(explanation)
+}
+
+/// Writes the explanation of each of `codes` to `dir` as `<code>.md`, so the editor can open it
+/// from a diagnostic. Codes without an explanation are skipped.
+pub fn write_explanations<'a>(
+    dir: &Path,
+    codes: impl IntoIterator<Item = &'a str>,
+) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for code in codes {
+        if let Some(explanation) = explanation(code) {
+            std::fs::write(dir.join(format!("{code}.md")), explanation)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_code_is_explained() {
+        for code in aml_core::codes::CODES {
+            let explanation = explanation(code.code)
+                .unwrap_or_else(|| panic!("{} has no explanation", code.code));
+            assert!(explanation.starts_with(&format!("# {}: `{}`", code.code, code.name)));
+        }
+    }
+
+    #[test]
+    fn test_write_explanations() {
+        let dir = std::env::temp_dir().join(format!("aml-docs-{}", std::process::id()));
+        write_explanations(&dir, ["AML0001", "AML9999"]).unwrap();
+
+        let written = std::fs::read_to_string(dir.join("AML0001.md")).unwrap();
+        assert_eq!(Some(written.as_str()), explanation("AML0001"));
+        assert!(!dir.join("AML9999.md").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,412 @@
+//! Reads what templates depend on from the Rust sources of the project, without rust-analyzer:
+//! the template of each component, from the calls registering them, its state, from the structs
+//! deriving `State`, and the queries it runs on its elements.
+
+mod rust_type;
+mod source;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use aml_semantic::{ElementQuery, StateField, StateSchema};
+
+pub use rust_type::RustType;
+use source::{Registration, RustFile, StateStruct};
+
+/// The Rust sources of a project.
+#[derive(Debug, Default)]
+pub struct RustWorkspace {
+    files: HashMap<PathBuf, RustFile>,
+}
+
+impl RustWorkspace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads every `.rs` file under `root`, skipping `target` and hidden directories.
+    pub fn load(root: &Path) -> Self {
+        let mut workspace = Self::new();
+        let mut directories = vec![root.to_path_buf()];
+
+        while let Some(directory) = directories.pop() {
+            let Ok(entries) = std::fs::read_dir(&directory) else { continue };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+
+                if path.is_dir() {
+                    if name != "target" && !name.starts_with('.') {
+                        directories.push(path);
+                    }
+                } else if is_rust_file(&path)
+                    && let Ok(text) = std::fs::read_to_string(&path)
+                {
+                    workspace.set_file(&path, &text);
+                }
+            }
+        }
+
+        workspace
+    }
+
+    /// Parses a source file. A file that does not parse keeps what was read from it before.
+    pub fn set_file(&mut self, path: &Path, text: &str) {
+        if let Some(file) = RustFile::parse(text) {
+            self.files.insert(path.into(), file);
+        }
+    }
+
+    pub fn remove_file(&mut self, path: &Path) {
+        self.files.remove(path);
+    }
+
+    /// The template of every registered component, by name. Templates are relative to the crate
+    /// registering them.
+    pub fn components(&self) -> HashMap<String, PathBuf> {
+        let mut components = HashMap::new();
+        for (path, registration) in self.registrations() {
+            let template = crate_dir(path).join(&registration.template);
+            components
+                .entry(registration.name.clone())
+                .or_insert(template);
+        }
+
+        components
+    }
+
+    /// The state of every component whose Rust struct is known, by component name. Registered
+    /// components get the state they are registered with. Others are named after their type,
+    /// `impl Component for TodoList` backs `@todo_list`, and a state no component uses after its
+    /// struct, `TodoListState` also backs `@todo_list`.
+    pub fn states(&self) -> HashMap<String, StateSchema> {
+        // the first struct of a name wins, files are sorted so it is always the same one
+        let mut structs = HashMap::new();
+        for (path, file) in self.sorted_files() {
+            for state in &file.states {
+                structs.entry(state.name.as_str()).or_insert((state, path));
+            }
+        }
+
+        let impls = self
+            .sorted_files()
+            .flat_map(|(_, file)| &file.components)
+            .map(|component| (component.component.as_str(), component.state.name.as_str()))
+            .collect::<Vec<_>>();
+        let state_of = |component: &str| {
+            impls
+                .iter()
+                .find(|(name, _)| *name == component)
+                .map(|(_, state)| *state)
+        };
+
+        let mut states = HashMap::new();
+        let mut used = vec![];
+        let mut registered = vec![];
+        for (_, registration) in self.registrations() {
+            let component = registration.component.as_deref();
+            registered.extend(component);
+
+            let state = registration
+                .state
+                .as_deref()
+                .or(component.and_then(state_of));
+            let Some((state, path)) = state.and_then(|state| structs.get(state)) else { continue };
+            states
+                .entry(registration.name.clone())
+                .or_insert_with(|| state_schema(state, path, &structs));
+            used.push(state.name.as_str());
+        }
+
+        for (component, state) in impls.iter() {
+            if registered.contains(component) {
+                continue;
+            }
+
+            let Some((state, path)) = structs.get(state) else { continue };
+            states
+                .entry(snake_case(component))
+                .or_insert_with(|| state_schema(state, path, &structs));
+            used.push(state.name.as_str());
+        }
+
+        for (state, path) in structs.values() {
+            if used.contains(&state.name.as_str()) {
+                continue;
+            }
+
+            if let Some(name) = state.name.strip_suffix("State")
+                && !name.is_empty()
+            {
+                let schema = state_schema(state, path, &structs);
+                states.entry(snake_case(name)).or_insert(schema);
+            }
+        }
+
+        states
+    }
+
+    /// The queries components run on their elements, named after the component as in
+    /// [`RustWorkspace::states`].
+    pub fn element_queries(&self) -> Vec<ElementQuery> {
+        let name_of = |component: &str| {
+            self.registrations()
+                .find(|(_, registration)| registration.component.as_deref() == Some(component))
+                .map(|(_, registration)| registration.name.clone())
+                .unwrap_or_else(|| snake_case(component))
+        };
+
+        self.sorted_files()
+            .flat_map(|(path, file)| file.queries.iter().map(move |query| (path, query)))
+            .map(|(path, query)| ElementQuery {
+                file: path.clone(),
+                component: query.component.as_deref().map(name_of),
+                selector: query.selector.clone(),
+                location: query.location,
+                value_location: query.value_location,
+            })
+            .collect()
+    }
+
+    fn sorted_files(&self) -> impl Iterator<Item = (&PathBuf, &RustFile)> {
+        let mut files = self.files.iter().collect::<Vec<_>>();
+        files.sort_by_key(|(path, _)| *path);
+        files.into_iter()
+    }
+
+    fn registrations(&self) -> impl Iterator<Item = (&PathBuf, &Registration)> {
+        self.sorted_files()
+            .flat_map(|(path, file)| file.registrations.iter().map(move |r| (path, r)))
+    }
+}
+
+/// The directory of the crate a source file belongs to, which the application usually runs in.
+fn crate_dir(file: &Path) -> &Path {
+    let parent = file.parent().unwrap_or(file);
+    parent
+        .ancestors()
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .unwrap_or(parent)
+}
+
+fn state_schema(
+    state: &StateStruct,
+    path: &Path,
+    structs: &HashMap<&str, (&StateStruct, &PathBuf)>,
+) -> StateSchema {
+    let structs = structs
+        .iter()
+        .map(|(name, (state, _))| (*name, *state))
+        .collect();
+
+    let fields = state
+        .fields
+        .iter()
+        .map(|(name, ty, location)| StateField {
+            name: name.clone(),
+            value_type: ty.value_type(&structs),
+            rust_type: Some(ty.to_string()),
+            location: *location,
+        })
+        .collect();
+
+    StateSchema {
+        fields,
+        definition: Some(path.into()),
+    }
+}
+
+pub fn is_rust_file(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "rs")
+}
+
+/// Converts a type name to the name of its template, `TodoList` is `todo_list`.
+fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len());
+    for (index, char) in name.char_indices() {
+        if char.is_uppercase() {
+            if index > 0 {
+                snake.push('_');
+            }
+            snake.extend(char.to_lowercase());
+        } else {
+            snake.push(char);
+        }
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+use anathema::component::*;
+use anathema::state::{List, Maybe, State, Value};
+
+#[derive(Debug, State)]
+struct Item {
+    label: Value<String>,
+    done: Value<bool>,
+}
+
+#[derive(Default, State)]
+pub struct TodoState {
+    items: Value<List<Item>>,
+    count: Value<usize>,
+    selected: Value<Maybe<Item>>,
+    #[state_ignore]
+    cache: Vec<String>,
+}
+
+struct TodoList;
+
+impl Component for TodoList {
+    type Message = ();
+    type State = TodoState;
+}
+
+mod settings {
+    #[derive(State)]
+    pub struct SettingsState {
+        theme: Value<String>,
+    }
+}
+"#;
+
+    #[test]
+    fn test_component_states() {
+        let mut workspace = RustWorkspace::new();
+        workspace.set_file(Path::new("src/main.rs"), SOURCE);
+        let states = workspace.states();
+
+        let todo = &states["todo_list"];
+        let fields = todo
+            .fields
+            .iter()
+            .map(|field| {
+                let rust_type = field.rust_type.as_deref().unwrap();
+                (field.name.as_str(), rust_type, field.value_type.to_string())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            vec![
+                (
+                    "items",
+                    "Value<List<Item>>",
+                    "list<{label: string, done: bool}>".into()
+                ),
+                ("count", "Value<usize>", "number".into()),
+                (
+                    "selected",
+                    "Value<Maybe<Item>>",
+                    "{label: string, done: bool} | null".into()
+                ),
+            ]
+        );
+        assert_eq!(todo.definition.as_deref(), Some(Path::new("src/main.rs")));
+
+        // a state no component uses is matched by its name
+        assert!(states["settings"].field("theme").is_some());
+        assert!(!states.contains_key("item"));
+    }
+
+    #[test]
+    fn test_registered_components() {
+        let main = r#"
+fn main() {
+    let mut builder = Runtime::builder(document, &backend);
+    builder
+        .component("todo", "templates/widgets/todo.aml", TodoList, TodoState::new())
+        .unwrap();
+    builder.prototype("row", "templates/row.aml", || Row, || ()).unwrap();
+    builder.default::<Index>("index", "templates/index.aml").unwrap();
+    builder.component("inline", "text 'hello'", Inline, ());
+}
+"#;
+        let mut workspace = RustWorkspace::new();
+        workspace.set_file(Path::new("src/todo.rs"), SOURCE);
+        workspace.set_file(Path::new("src/main.rs"), main);
+
+        let components = workspace.components();
+        let mut names = components.keys().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["index", "row", "todo"]);
+        assert_eq!(components["todo"], Path::new("templates/widgets/todo.aml"));
+
+        // the registered name replaces the one derived from the component type
+        let states = workspace.states();
+        assert!(states["todo"].field("items").is_some());
+        assert!(!states.contains_key("todo_list"));
+    }
+
+    #[test]
+    fn test_element_queries() {
+        let source = r#"
+impl Component for TodoList {
+    type State = TodoState;
+
+    fn on_key(&mut self, key: KeyEvent, _: &mut TodoState, mut children: Children<'_, '_>) {
+        children.elements().by_tag("overflow").first(|el, _| el.scroll_down());
+        children.elements().by_attribute("id", "list").each(|_, _| ());
+    }
+}
+
+fn main() {
+    builder.component("todo", "templates/todo.aml", TodoList, TodoState::new());
+    elements.by_attribute("selected", true);
+}
+"#;
+        let mut workspace = RustWorkspace::new();
+        workspace.set_file(Path::new("src/main.rs"), source);
+
+        let queries = workspace.element_queries();
+        let queries = queries
+            .iter()
+            .map(|query| {
+                let literal = &source[query.location.to_range()];
+                (
+                    query.component.as_deref(),
+                    query.selector.to_string(),
+                    literal,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            queries,
+            vec![
+                (Some("todo"), "'overflow' element".into(), "\"overflow\""),
+                (
+                    Some("todo"),
+                    "element with 'id' set to 'list'".into(),
+                    "\"id\""
+                ),
+                (
+                    None,
+                    "element with 'selected' set to 'true'".into(),
+                    "\"selected\""
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unparsable_file_keeps_previous_states() {
+        let mut workspace = RustWorkspace::new();
+        let path = Path::new("src/main.rs");
+        workspace.set_file(path, SOURCE);
+        workspace.set_file(path, "struct Broken {");
+        assert!(workspace.states().contains_key("todo_list"));
+
+        workspace.remove_file(path);
+        assert!(workspace.states().is_empty());
+    }
+
+    #[test]
+    fn test_snake_case() {
+        assert_eq!(snake_case("TodoList"), "todo_list");
+        assert_eq!(snake_case("Button"), "button");
+    }
+}
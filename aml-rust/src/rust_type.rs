@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use aml_semantic::ValueType;
+
+use crate::source::StateStruct;
+
+/// Nested state structs deeper than this are left unknown, which also stops recursive states.
+const MAX_DEPTH: usize = 8;
+
+/// The shape of a Rust type as far as templates are concerned: the last segment of its path and
+/// its type arguments, e.g. `Value<List<Item>>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RustType {
+    pub name: String,
+    pub args: Vec<RustType>,
+}
+
+impl RustType {
+    /// Types that don't map to a template value, e.g. tuples or function pointers.
+    fn other() -> Self {
+        Self {
+            name: "_".into(),
+            args: vec![],
+        }
+    }
+
+    pub fn from_syn(ty: &syn::Type) -> Self {
+        match ty {
+            syn::Type::Path(path) => {
+                let Some(segment) = path.path.segments.last() else { return Self::other() };
+                let args = match &segment.arguments {
+                    syn::PathArguments::AngleBracketed(arguments) => arguments
+                        .args
+                        .iter()
+                        .filter_map(|argument| match argument {
+                            syn::GenericArgument::Type(ty) => Some(Self::from_syn(ty)),
+                            _ => None,
+                        })
+                        .collect(),
+                    _ => vec![],
+                };
+
+                Self {
+                    name: segment.ident.to_string(),
+                    args,
+                }
+            }
+            syn::Type::Reference(reference) => Self::from_syn(&reference.elem),
+            syn::Type::Paren(paren) => Self::from_syn(&paren.elem),
+            syn::Type::Group(group) => Self::from_syn(&group.elem),
+            _ => Self::other(),
+        }
+    }
+
+    /// Maps the type onto the type of the value templates see, other state structs being records
+    /// of their fields.
+    pub(crate) fn value_type(&self, structs: &HashMap<&str, &StateStruct>) -> ValueType {
+        self.value_type_at(structs, 0)
+    }
+
+    fn value_type_at(&self, structs: &HashMap<&str, &StateStruct>, depth: usize) -> ValueType {
+        let inner = |ty: &RustType| ty.value_type_at(structs, depth);
+
+        match (self.name.as_str(), self.args.as_slice()) {
+            ("Value" | "Box" | "Rc" | "Arc", [ty]) => inner(ty),
+            ("String" | "str" | "char", []) => ValueType::String,
+            (
+                "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64"
+                | "i128" | "isize" | "f32" | "f64",
+                [],
+            ) => ValueType::Number,
+            ("bool", []) => ValueType::Boolean,
+            ("Color" | "Hex", []) => ValueType::Hex,
+            ("List" | "Vec" | "VecDeque", [ty]) => ValueType::List(Box::new(inner(ty))),
+            ("Map", [ty]) => ValueType::Map(Box::new(ValueType::String), Box::new(inner(ty))),
+            ("HashMap" | "BTreeMap", [key, ty]) => {
+                ValueType::Map(Box::new(inner(key)), Box::new(inner(ty)))
+            }
+            ("Maybe" | "Option" | "Nullable", [ty]) => inner(ty).optional(),
+            (name, []) if depth < MAX_DEPTH => match structs.get(name) {
+                Some(state) => ValueType::Record(
+                    state
+                        .fields
+                        .iter()
+                        .map(|(name, ty, _)| (name.clone(), ty.value_type_at(structs, depth + 1)))
+                        .collect(),
+                ),
+                None => ValueType::Unknown,
+            },
+            _ => ValueType::Unknown,
+        }
+    }
+}
+
+impl std::fmt::Display for RustType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if self.args.is_empty() {
+            return Ok(());
+        }
+
+        write!(f, "<")?;
+        for (index, arg) in self.args.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{arg}")?;
+        }
+        write!(f, ">")
+    }
+}
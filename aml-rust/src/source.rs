@@ -0,0 +1,279 @@
+use std::path::PathBuf;
+
+use aml_core::Location;
+use aml_semantic::Selector;
+use syn::punctuated::Punctuated;
+use syn::visit::{self, Visit};
+use syn::{Attribute, Expr, Lit, Token};
+
+use crate::rust_type::RustType;
+
+/// What a Rust source file declares that templates depend on.
+#[derive(Debug, Default)]
+pub(crate) struct RustFile {
+    pub states: Vec<StateStruct>,
+    pub components: Vec<ComponentImpl>,
+    pub registrations: Vec<Registration>,
+    pub queries: Vec<Query>,
+    /// The component whose `impl Component` is being visited.
+    component: Option<String>,
+}
+
+/// A struct deriving `State`.
+#[derive(Debug)]
+pub(crate) struct StateStruct {
+    pub name: String,
+    /// The name, type and location of the name of each field.
+    pub fields: Vec<(String, RustType, Location)>,
+}
+
+/// An `impl Component for Button { type State = ButtonState; }`.
+#[derive(Debug)]
+pub(crate) struct ComponentImpl {
+    pub component: String,
+    pub state: RustType,
+}
+
+/// A component registered with the runtime, e.g.
+/// `builder.component("todo", "templates/todo.aml", TodoList, TodoState::new())`.
+#[derive(Debug)]
+pub(crate) struct Registration {
+    pub name: String,
+    /// The template as written, relative to the directory the application runs in.
+    pub template: PathBuf,
+    /// The type of the component, if it can be told from the arguments.
+    pub component: Option<String>,
+    /// The type of the state, if it can be told from the arguments.
+    pub state: Option<String>,
+}
+
+/// A query on the elements of a component, e.g. `elements.by_tag("overflow")` or
+/// `elements.by_attribute("id", "list")`.
+#[derive(Debug)]
+pub(crate) struct Query {
+    /// The type of the component whose methods run the query.
+    pub component: Option<String>,
+    pub selector: Selector,
+    /// The location of the tag or of the attribute name.
+    pub location: Location,
+    pub value_location: Option<Location>,
+}
+
+impl RustFile {
+    /// Returns `None` if the file does not parse, e.g. while it is being edited.
+    pub fn parse(text: &str) -> Option<Self> {
+        let file = syn::parse_file(text);
+        let mut rust_file = Self::default();
+        if let Ok(file) = &file {
+            rust_file.visit_file(file);
+        }
+
+        // locations are read while visiting, the spans of the file are not needed anymore
+        proc_macro2::extra::invalidate_current_thread_spans();
+        file.ok().map(|_| rust_file)
+    }
+}
+
+impl<'ast> Visit<'ast> for RustFile {
+    fn visit_item_struct(&mut self, item: &'ast syn::ItemStruct) {
+        if derives_state(&item.attrs) {
+            self.states.push(StateStruct {
+                name: item.ident.to_string(),
+                fields: state_fields(&item.fields),
+            });
+        }
+
+        visit::visit_item_struct(self, item);
+    }
+
+    fn visit_item_impl(&mut self, item: &'ast syn::ItemImpl) {
+        let outer = self.component.clone();
+        if let Some(component) = component_impl(item) {
+            self.component = Some(component.component.clone());
+            self.components.push(component);
+        }
+
+        visit::visit_item_impl(self, item);
+        self.component = outer;
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        if let Some(registration) = registration(call) {
+            self.registrations.push(registration);
+        }
+
+        if let Some(query) = query(call, self.component.clone()) {
+            self.queries.push(query);
+        }
+
+        visit::visit_expr_method_call(self, call);
+    }
+}
+
+fn derives_state(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("derive"))
+        .filter_map(|attr| {
+            attr.parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)
+                .ok()
+        })
+        .flatten()
+        .any(|path| {
+            path.segments
+                .last()
+                .is_some_and(|segment| segment.ident == "State")
+        })
+}
+
+/// The named fields of a state, without the ones marked `#[state_ignore]`.
+fn state_fields(fields: &syn::Fields) -> Vec<(String, RustType, Location)> {
+    fields
+        .iter()
+        .filter(|field| {
+            !field
+                .attrs
+                .iter()
+                .any(|attr| attr.path().is_ident("state_ignore"))
+        })
+        .filter_map(|field| {
+            let ident = field.ident.as_ref()?;
+            let range = ident.span().byte_range();
+            let location = Location {
+                start_byte: range.start,
+                end_byte: range.end,
+            };
+            Some((ident.to_string(), RustType::from_syn(&field.ty), location))
+        })
+        .collect()
+}
+
+fn component_impl(item: &syn::ItemImpl) -> Option<ComponentImpl> {
+    let (_, trait_path, _) = item.trait_.as_ref()?;
+    if trait_path.segments.last()?.ident != "Component" {
+        return None;
+    }
+
+    let component = match &*item.self_ty {
+        syn::Type::Path(path) => path.path.segments.last()?.ident.to_string(),
+        _ => return None,
+    };
+
+    let state = item.items.iter().find_map(|item| match item {
+        syn::ImplItem::Type(ty) if ty.ident == "State" => Some(RustType::from_syn(&ty.ty)),
+        _ => None,
+    })?;
+
+    Some(ComponentImpl { component, state })
+}
+
+/// Reads a call registering a component: `component`, `prototype` or `default`, along with their
+/// `register_` forms, taking the name and the path of the template as string literals.
+fn registration(call: &syn::ExprMethodCall) -> Option<Registration> {
+    let method = call.method.to_string();
+    let method = method.strip_prefix("register_").unwrap_or(&method);
+    if !matches!(method, "component" | "prototype" | "default") {
+        return None;
+    }
+
+    let mut args = call.args.iter();
+    let name = string_literal(args.next()?)?;
+    let template = string_literal(args.next()?)?;
+    if !template.ends_with(".aml") {
+        return None;
+    }
+
+    // `default::<Index>("index", "templates/index.aml")` names the component in the turbofish
+    let turbofish = call.turbofish.as_ref().and_then(|turbofish| {
+        turbofish.args.iter().find_map(|arg| match arg {
+            syn::GenericArgument::Type(syn::Type::Path(path)) => path_type(&path.path),
+            _ => None,
+        })
+    });
+
+    Some(Registration {
+        name,
+        template: template.into(),
+        component: turbofish.or_else(|| type_name(args.next()?)),
+        state: args.next().and_then(type_name),
+    })
+}
+
+fn query(call: &syn::ExprMethodCall, component: Option<String>) -> Option<Query> {
+    let mut args = call.args.iter();
+    let (name, location) = literal(args.next()?)?;
+
+    let (selector, value_location) = match call.method.to_string().as_str() {
+        "by_tag" => (Selector::Tag(name), None),
+        "by_attribute" => {
+            // the value can be anything turning into a value, only literals can be checked
+            let value = args.next().and_then(literal);
+            let value_location = value.as_ref().map(|(_, location)| *location);
+            let selector = Selector::Attribute {
+                name,
+                value: value.map(|(value, _)| value),
+            };
+            (selector, value_location)
+        }
+        _ => return None,
+    };
+
+    Some(Query {
+        component,
+        selector,
+        location,
+        value_location,
+    })
+}
+
+/// A literal as it is written in a template, along with its location.
+fn literal(expr: &Expr) -> Option<(String, Location)> {
+    let Expr::Lit(literal) = expr else { return None };
+    let value = match &literal.lit {
+        Lit::Str(literal) => literal.value(),
+        Lit::Int(literal) => literal.base10_digits().into(),
+        Lit::Float(literal) => literal.base10_digits().into(),
+        Lit::Bool(literal) => literal.value.to_string(),
+        _ => return None,
+    };
+
+    let range = literal.lit.span().byte_range();
+    let location = Location {
+        start_byte: range.start,
+        end_byte: range.end,
+    };
+    Some((value, location))
+}
+
+fn string_literal(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(syn::ExprLit {
+            lit: Lit::Str(literal),
+            ..
+        }) => Some(literal.value()),
+        _ => None,
+    }
+}
+
+/// The type an expression builds, e.g. `TodoState` in `TodoState::new()` or `|| TodoState`.
+fn type_name(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Path(path) => path_type(&path.path),
+        Expr::Struct(strukt) => path_type(&strukt.path),
+        Expr::Call(call) => type_name(&call.func),
+        Expr::MethodCall(call) => type_name(&call.receiver),
+        Expr::Closure(closure) => type_name(&closure.body),
+        Expr::Paren(paren) => type_name(&paren.expr),
+        Expr::Group(group) => type_name(&group.expr),
+        _ => None,
+    }
+}
+
+/// The last type in a path, e.g. `TodoState` in `state::TodoState::new`.
+fn path_type(path: &syn::Path) -> Option<String> {
+    path.segments
+        .iter()
+        .rev()
+        .map(|segment| segment.ident.to_string())
+        .find(|name| name.starts_with(char::is_uppercase))
+}
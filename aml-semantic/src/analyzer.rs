@@ -1,17 +1,54 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::path::PathBuf;
 
-use aml_core::Location;
-use aml_syntax::Ast;
+use aml_config::ColorDepth;
+use aml_core::{Code, Location, Severity, codes};
 use aml_syntax::ast::*;
-use aml_token::{Operator, Primitive};
+use aml_syntax::{Ast, syntax_diagnostics};
+use aml_token::{Container, Hex, Operator, Primitive, TokenKind};
 
+use crate::color::{
+    PaletteColor, contrast_ratio, is_color_name, nearest_color, normalize_color_name, parse_hex,
+};
+use crate::evaluator::{self, EvalError, Evaluator, Value};
+use crate::functions;
 use crate::global_scope::{GlobalScope, GlobalSymbol};
-use crate::symbol_table::{SymbolTable, SymbolType, ValueType};
+use crate::interface::{ComponentFiles, ComponentInterface};
+use crate::lints::{self, Lint, LintLevels, Suppressions};
+use crate::queries::{ElementAttribute, Selector, TemplateElement};
+use crate::state::StateSchema;
+use crate::symbol_table::{Reference, ReferenceTarget, SymbolTable, SymbolType, ValueType};
 
-#[derive(Debug)]
+#[derive(Debug, Default, Clone)]
 pub struct SemanticInfo {
     pub symbol_table: SymbolTable,
     pub diagnostics: Vec<SemanticDiagnostic>,
+    pub computed_values: Vec<ComputedValue>,
+    /// The interface of the template when it is used as a component.
+    pub interface: ComponentInterface,
+    /// The state the template was analyzed with.
+    pub state: Option<StateSchema>,
+    /// The elements of the template, which components can query.
+    pub elements: Vec<TemplateElement>,
+}
+
+impl SemanticInfo {
+    /// Returns the innermost computed value at the given byte offset.
+    pub fn computed_value_at(&self, byte_offset: usize) -> Option<&ComputedValue> {
+        self.computed_values
+            .iter()
+            .filter(|computed| computed.location.contains(byte_offset))
+            .min_by_key(|computed| computed.location.end_byte - computed.location.start_byte)
+    }
+}
+
+/// The value of a non-literal expression that could be evaluated during analysis, e.g. `7` for
+/// `width: 5 + 2`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputedValue {
+    pub location: Location,
+    pub value: Value,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -19,6 +56,97 @@ pub struct SemanticDiagnostic {
     pub location: Location,
     pub message: String,
     pub severity: DiagnosticSeverity,
+    /// The stable code of the diagnostic, see [`aml_core::codes`].
+    pub code: &'static Code,
+    pub related: Vec<RelatedInformation>,
+    pub tags: Vec<DiagnosticTag>,
+    /// The name of the lint reporting the diagnostic, see [`lints`].
+    pub lint: Option<&'static str>,
+    pub fixes: Vec<Fix>,
+}
+
+impl SemanticDiagnostic {
+    pub fn new(
+        code: &'static Code,
+        location: Location,
+        message: String,
+        severity: DiagnosticSeverity,
+    ) -> Self {
+        Self {
+            location,
+            message,
+            severity,
+            code,
+            related: Vec::new(),
+            tags: Vec::new(),
+            lint: None,
+            fixes: Vec::new(),
+        }
+    }
+
+    /// A diagnostic of a lint, with the severity it has when it warns.
+    pub fn lint(lint: &'static Lint, location: Location, message: String) -> Self {
+        Self {
+            lint: Some(lint.name()),
+            ..Self::new(lint.code, location, message, lint.severity.clone())
+        }
+    }
+
+    pub fn with_related(mut self, location: Location, message: impl Into<String>) -> Self {
+        self.related.push(RelatedInformation {
+            location,
+            message: message.into(),
+            file: None,
+        });
+        self
+    }
+
+    pub fn with_related_in(
+        mut self,
+        file: PathBuf,
+        location: Location,
+        message: impl Into<String>,
+    ) -> Self {
+        self.related.push(RelatedInformation {
+            location,
+            message: message.into(),
+            file: Some(file),
+        });
+        self
+    }
+
+    pub fn with_tag(mut self, tag: DiagnosticTag) -> Self {
+        self.tags.push(tag);
+        self
+    }
+
+    /// Suggests replacing the code at a location of the file being analyzed.
+    pub fn with_fix(mut self, location: Location, replacement: impl Into<String>) -> Self {
+        let replacement = replacement.into();
+        self.fixes.push(Fix {
+            title: format!("Replace with {replacement}"),
+            location,
+            replacement,
+        });
+        self
+    }
+}
+
+/// A change to the file being analyzed that fixes a diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Fix {
+    pub title: String,
+    pub location: Location,
+    pub replacement: String,
+}
+
+/// Secondary location attached to a diagnostic, e.g. the first occurrence of a duplicate.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RelatedInformation {
+    pub location: Location,
+    pub message: String,
+    /// The file the location belongs to, or `None` for the file being analyzed.
+    pub file: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -26,68 +154,428 @@ pub enum DiagnosticSeverity {
     Error,
     Warning,
     Info,
+    Hint,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiagnosticTag {
+    /// The code is not needed, editors usually render it faded out.
+    Unnecessary,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalyzerOptions {
+    /// The level of each lint, see [`lints`].
+    pub lints: LintLevels,
+    /// The lowest contrast ratio between the foreground and the background of text.
+    pub min_contrast_ratio: f64,
+    /// The colours the targeted terminals can show, `None` for any colour.
+    pub color_depth: Option<ColorDepth>,
+}
+
+impl Default for AnalyzerOptions {
+    fn default() -> Self {
+        Self {
+            lints: LintLevels::default(),
+            min_contrast_ratio: aml_config::DEFAULT_MIN_CONTRAST_RATIO,
+            color_depth: None,
+        }
+    }
+}
+
+/// The colours an element is drawn with, either set on it or inherited from its ancestors.
+/// Colours that are not constant are unknown, as is the default colour of the terminal.
+#[derive(Debug, Default, Clone, Copy)]
+struct Colors {
+    /// The colour along with the location of the attribute value setting it.
+    foreground: Option<(Hex, Location)>,
+    background: Option<(Hex, Location)>,
 }
 
 #[derive(Debug)]
 pub struct SemanticAnalyzer<'src> {
     symbol_table: SymbolTable,
     diagnostics: Vec<SemanticDiagnostic>,
+    computed_values: Vec<ComputedValue>,
+    interface: ComponentInterface,
+    state: Option<StateSchema>,
+    elements: Vec<TemplateElement>,
     content: &'src str,
-    global_scope: &'src mut GlobalScope,
+    global_scope: &'src GlobalScope,
+    file_path: PathBuf,
+    components: ComponentFiles,
+    /// The templates of the workspace, the only ones components can be made of.
+    templates: Vec<PathBuf>,
+    options: AnalyzerOptions,
+    suppressions: Suppressions,
+    /// The selectors of the queries that can find the elements of the template.
+    selectors: Vec<Selector>,
+    colors: Colors,
+    inside_text: bool,
+    /// Whether the template is used as a component, reading names it does not declare from the
+    /// attributes of its call sites instead.
+    is_component: bool,
+    /// Only the interface of the template is collected, nothing is reported.
+    collecting_interface: bool,
 }
 
 impl<'src> SemanticAnalyzer<'src> {
-    pub fn new(content: &'src str, global_scope: &'src mut GlobalScope) -> SemanticAnalyzer<'src> {
+    pub fn new(content: &'src str, global_scope: &'src GlobalScope) -> SemanticAnalyzer<'src> {
         SemanticAnalyzer {
             symbol_table: SymbolTable::new(),
             diagnostics: Vec::new(),
+            computed_values: Vec::new(),
+            interface: ComponentInterface::default(),
+            state: None,
+            elements: Vec::new(),
             content,
             global_scope,
+            file_path: PathBuf::new(),
+            components: ComponentFiles::default(),
+            templates: Vec::new(),
+            options: AnalyzerOptions::default(),
+            suppressions: Suppressions::default(),
+            selectors: Vec::new(),
+            colors: Colors::default(),
+            inside_text: false,
+            is_component: false,
+            collecting_interface: false,
         }
     }
 
+    /// Sets the path of the file being analyzed, used to tell its own global declarations apart
+    /// from the ones declared in other templates.
+    pub fn with_file_path(mut self, file_path: PathBuf) -> Self {
+        self.file_path = file_path;
+        self
+    }
+
+    /// Sets the directory component templates are looked up in, `@name` being `name.aml` in it.
+    /// Missing templates are only reported when it is set, or for registered components.
+    pub fn with_templates_dir(mut self, templates_dir: PathBuf) -> Self {
+        self.components.templates_dir = Some(templates_dir);
+        self
+    }
+
+    /// Sets where component templates are looked up, see [`ComponentFiles`].
+    pub fn with_components(mut self, components: ComponentFiles) -> Self {
+        self.components = components;
+        self
+    }
+
+    /// Sets the templates of the workspace, a component whose template is not among them is
+    /// reported as missing.
+    pub fn with_templates(mut self, templates: Vec<PathBuf>) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    pub fn with_options(mut self, options: AnalyzerOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the lints allowed by the comments of the template.
+    pub fn with_suppressions(mut self, suppressions: Suppressions) -> Self {
+        self.suppressions = suppressions;
+        self
+    }
+
+    /// Sets the selectors of the element queries the components using the template run, the
+    /// elements with an `id` none of them finds are reported. Nothing is reported without any.
+    pub fn with_selectors(mut self, selectors: Vec<Selector>) -> Self {
+        self.selectors = selectors;
+        self
+    }
+
+    /// Declares the fields of the state the template is rendered with.
+    pub fn with_state(mut self, schema: &StateSchema) -> Self {
+        self.symbol_table = SymbolTable::with_state(schema);
+        self.state = Some(schema.clone());
+        self
+    }
+
     pub fn analyze(&mut self, ast: &Ast) -> SemanticInfo {
+        self.interface.definition = self.file_path.clone();
+        self.is_component = self.is_component();
+        self.report_syntax_errors(ast);
+
         for node in ast.nodes.iter() {
             self.analyze_node(node);
         }
 
+        self.report_unused_symbols();
+        self.report_unqueried_elements();
+        self.report_unknown_lints();
+
         SemanticInfo {
             symbol_table: std::mem::take(&mut self.symbol_table),
             diagnostics: std::mem::take(&mut self.diagnostics),
+            computed_values: std::mem::take(&mut self.computed_values),
+            interface: std::mem::take(&mut self.interface),
+            state: self.state.clone(),
+            elements: std::mem::take(&mut self.elements),
         }
     }
 
-    pub fn collect_globals(&'src mut self, ast: &'src Ast) {
-        ast.accept(&mut GlobalCollector {
-            file_path: PathBuf::new(),
-            content: self.content,
-            analyzer: self,
-        });
+    /// Infers the interface of the template when used as a component. It only depends on the
+    /// globals and the state, not on the interface of other components, and nothing is reported.
+    pub fn collect_interface(&mut self, ast: &Ast) -> ComponentInterface {
+        self.interface.definition = self.file_path.clone();
+        self.collecting_interface = true;
+        for node in ast.nodes.iter() {
+            self.analyze_node(node);
+        }
+
+        std::mem::take(&mut self.interface)
+    }
+
+    /// Collects the global declarations of the template, owned by the analyzer's file path.
+    pub fn collect_globals(&mut self, ast: &Ast) -> Vec<GlobalSymbol> {
+        let mut collector = GlobalCollector::default();
+        ast.accept(&mut collector);
+
+        collector
+            .declarations
+            .into_iter()
+            .filter_map(|decl| {
+                let AstNode::Identifier(location) = *decl.name else { return None };
+                let value_type = self.analyze_expression(&decl.value);
+                let value = self.evaluate(&decl.value);
+
+                Some(GlobalSymbol {
+                    name: self.content[location.to_range()].into(),
+                    location: decl.location,
+                    symbol_type: SymbolType::Variable(value_type),
+                    definition: self.file_path.clone(),
+                    value,
+                })
+            })
+            .collect()
     }
 
     fn declare_variable(&mut self, declaration: &Declaration) {
-        let name = self.get_node_text(&declaration.name);
+        // keywords used as names are already reported by the parser
+        let AstNode::Identifier(location) = *declaration.name else {
+            self.analyze_expression(&declaration.value);
+            return;
+        };
+        let name = &self.content[location.to_range()];
         let value_type = self.analyze_expression(&declaration.value);
         let symbol_type = SymbolType::Variable(value_type);
 
+        let value = self.evaluate(&declaration.value);
+
+        self.check_shadowing(name, location);
         self.symbol_table
-            .declare_symbol(name.into(), declaration.location, symbol_type);
+            .declare_symbol(name.into(), declaration.location, symbol_type, value);
+    }
+
+    fn check_conflicting_global(&mut self, declaration: &Declaration) {
+        let AstNode::Identifier(name_location) = *declaration.name else { return };
+        let name = &self.content[name_location.to_range()];
+
+        // declaring the same constant in several templates is not a conflict
+        let value = self.constant(&declaration.value).ok();
+        let others = self
+            .global_scope
+            .declarations(name)
+            .iter()
+            .filter(|global| {
+                global.definition != self.file_path || global.location != declaration.location
+            })
+            .filter(|global| value.is_none() || global.value != value)
+            .collect::<Vec<_>>();
+
+        if others.is_empty() {
+            return;
+        }
+
+        let diagnostic = others.into_iter().fold(
+            SemanticDiagnostic::lint(
+                &lints::CONFLICTING_GLOBALS,
+                name_location,
+                format!("conflicting declarations of global '{name}'"),
+            ),
+            |diagnostic, other| {
+                diagnostic.with_related_in(
+                    other.definition.clone(),
+                    other.location,
+                    format!("'{name}' is also declared here"),
+                )
+            },
+        );
+
+        self.push_diagnostic(diagnostic);
+    }
+
+    fn check_shadowing(&mut self, name: &str, location: Location) {
+        if let Some(symbol) = self.symbol_table.lookup_symbol(name) {
+            let shadowed = match symbol.symbol_type {
+                SymbolType::LoopBinding(_) => "loop binding",
+                SymbolType::State(_) => "state field",
+                _ => "local variable",
+            };
+            let diagnostic = SemanticDiagnostic::lint(
+                &lints::SHADOWING,
+                location,
+                format!("'{name}' shadows a {shadowed} with the same name"),
+            );
+            let definition = self
+                .state
+                .as_ref()
+                .and_then(|state| state.definition.clone());
+            let diagnostic = match (&symbol.symbol_type, definition) {
+                (SymbolType::State(_), Some(definition)) => diagnostic.with_related_in(
+                    definition,
+                    symbol.location,
+                    format!("'{name}' is declared in the state of this template"),
+                ),
+                (SymbolType::State(_), None) => diagnostic,
+                _ => diagnostic.with_related(symbol.location, format!("'{name}' is declared here")),
+            };
+
+            self.push_diagnostic(diagnostic);
+            return;
+        }
+
+        if let Some(global) = self.global_scope.lookup_symbol(name) {
+            let diagnostic = SemanticDiagnostic::lint(
+                &lints::SHADOWING,
+                location,
+                format!("'{name}' shadows the global '{name}'"),
+            )
+            .with_related_in(
+                global.definition.clone(),
+                global.location,
+                format!("global '{name}' is declared here"),
+            );
+
+            self.push_diagnostic(diagnostic);
+        }
+    }
+
+    fn report_syntax_errors(&mut self, ast: &Ast) {
+        for error in syntax_diagnostics(ast, self.content) {
+            let code = error
+                .code
+                .as_deref()
+                .and_then(codes::find)
+                .unwrap_or(&codes::UNEXPECTED_TOKEN);
+            let severity = match error.severity {
+                Severity::Error => DiagnosticSeverity::Error,
+                Severity::Warning => DiagnosticSeverity::Warning,
+                Severity::Info => DiagnosticSeverity::Info,
+                Severity::Hint => DiagnosticSeverity::Hint,
+            };
+            self.add_diagnostic(code, error.location, error.message, severity);
+        }
+    }
+
+    fn report_unused_symbols(&mut self) {
+        let mut unused = self
+            .symbol_table
+            .unused_symbols()
+            .filter(|symbol| !symbol.name.starts_with('_'))
+            .filter(|symbol| !matches!(symbol.symbol_type, SymbolType::State(_)))
+            .map(|symbol| {
+                let message = match symbol.symbol_type {
+                    SymbolType::LoopBinding(_) => format!("unused loop binding '{}'", symbol.name),
+                    _ => format!("unused local variable '{}'", symbol.name),
+                };
+
+                SemanticDiagnostic::lint(&lints::UNUSED_LOCAL, symbol.location, message)
+                    .with_tag(DiagnosticTag::Unnecessary)
+            })
+            .collect::<Vec<_>>();
+
+        unused.sort_by_key(|diagnostic| diagnostic.location);
+        unused
+            .into_iter()
+            .for_each(|diagnostic| self.push_diagnostic(diagnostic));
+    }
+
+    fn report_unqueried_elements(&mut self) {
+        if self.selectors.is_empty() {
+            return;
+        }
+
+        let unqueried = self
+            .elements
+            .iter()
+            .filter(|element| element.attributes.iter().any(|attr| attr.name == "id"))
+            .filter(|element| {
+                !self
+                    .selectors
+                    .iter()
+                    .any(|selector| selector.matches(element))
+            })
+            .map(|element| {
+                SemanticDiagnostic::lint(
+                    &lints::UNQUERIED_ELEMENT,
+                    element.location,
+                    format!(
+                        "no query in the Rust sources targets this '{}'",
+                        element.tag
+                    ),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        unqueried
+            .into_iter()
+            .for_each(|diagnostic| self.push_diagnostic(diagnostic));
+    }
+
+    fn report_unknown_lints(&mut self) {
+        let unknown = std::mem::take(&mut self.suppressions.unknown);
+        for (location, name) in unknown {
+            self.add_diagnostic(
+                &codes::UNKNOWN_LINT,
+                location,
+                format!("unknown lint '{name}'"),
+                DiagnosticSeverity::Warning,
+            );
+        }
     }
 
     fn analyze_node(&mut self, node: &AstNode) {
         match node {
-            AstNode::Text(text) => self.analyze_text_element(
-                &text.values,
-                &text.attributes,
-                &text.children,
-                text.location,
-            ),
-            AstNode::Span(span) => self.analyze_span_element(&span.values, &span.attributes),
-            AstNode::Container(container) => container
-                .children
-                .iter()
-                .for_each(|child| self.analyze_node(child)),
-            AstNode::Attribute(attribute) => _ = self.analyze_expression(&attribute.value),
+            AstNode::Text(text) => {
+                self.record_element(text.keyword, &text.attributes);
+                let parent_colors = self.push_colors(&text.attributes);
+                self.check_contrast(text.keyword);
+                self.analyze_text_element(
+                    &text.values,
+                    &text.attributes,
+                    &text.children,
+                    text.location,
+                );
+                self.colors = parent_colors;
+            }
+            AstNode::Span(span) => {
+                self.record_element(span.keyword, &span.attributes);
+                // a span drawn with the colours of its text is reported on the text
+                let parent_colors = self.push_colors(&span.attributes);
+                if !self.color_attributes(&span.attributes).is_empty() {
+                    self.check_contrast(span.keyword);
+                }
+                if !self.inside_text {
+                    self.add_diagnostic(
+                        &codes::SPAN_OUTSIDE_TEXT,
+                        span.keyword,
+                        "'span' must be inside a 'text' element".into(),
+                        DiagnosticSeverity::Error,
+                    );
+                }
+                self.analyze_span_element(&span.values, &span.attributes);
+                self.colors = parent_colors;
+            }
+            AstNode::Container(container) => self.analyze_container(container),
+            AstNode::Attribute(attribute) => {
+                self.analyze_expression(&attribute.value);
+                self.evaluate(&attribute.value);
+            }
             AstNode::Identifier { .. } => {}
             AstNode::String { .. } => {}
             AstNode::Primitive { .. } => {}
@@ -100,12 +588,49 @@ impl<'src> SemanticAnalyzer<'src> {
             }
             // Global declarations are collected before analyzing as they can be used before
             // being defined.
-            AstNode::Declaration { .. } => {}
+            AstNode::Declaration(declaration) => {
+                self.check_conflicting_global(declaration);
+                self.evaluate(&declaration.value);
+            }
 
-            AstNode::Component { .. } => {}
-            AstNode::ComponentSlot { .. } => {}
-            AstNode::For { .. } => {}
+            AstNode::Component(component) => self.analyze_component(component),
+            AstNode::ComponentSlot(slot) => {
+                let name = slot.name.text(self.content);
+                self.interface.add_slot(name);
+            }
+            AstNode::For(for_loop) => self.analyze_for(for_loop),
+        }
+    }
+
+    fn analyze_for(&mut self, for_loop: &For) {
+        let binding_type = match self.analyze_expression(&for_loop.value) {
+            ValueType::List(element_type) => *element_type,
+            _ => ValueType::Unknown,
+        };
+        self.infer_input_type(
+            &for_loop.value,
+            ValueType::List(Box::new(ValueType::Unknown)),
+        );
+
+        self.symbol_table.push_scope(None);
+
+        if let AstNode::Identifier(location) = *for_loop.binding {
+            let name = &self.content[location.to_range()];
+            self.check_shadowing(name, location);
+            self.symbol_table.declare_symbol(
+                name.into(),
+                location,
+                SymbolType::LoopBinding(binding_type),
+                None,
+            );
         }
+
+        for_loop
+            .children
+            .iter()
+            .for_each(|child| self.analyze_node(child));
+
+        self.symbol_table.pop_scope();
     }
 
     fn analyze_text_element(
@@ -116,166 +641,745 @@ impl<'src> SemanticAnalyzer<'src> {
         location: Location,
     ) {
         self.symbol_table.push_scope(None);
+        self.validate_attribute_names(attributes);
         attributes
             .items
             .iter()
             .for_each(|attr| self.analyze_node(attr));
         self.validate_text_element_value(value, location);
+        self.validate_text_element_children(children);
+
+        let inside_text = std::mem::replace(&mut self.inside_text, true);
         children.iter().for_each(|child| self.analyze_node(child));
+        self.inside_text = inside_text;
+
         self.symbol_table.pop_scope();
     }
 
-    fn analyze_span_element(&mut self, values: &[AstNode], attributes: &Attributes) {
-        attributes
+    fn analyze_component(&mut self, component: &Component) {
+        self.validate_attribute_names(&component.attributes);
+        component
+            .attributes
             .items
             .iter()
             .for_each(|attr| self.analyze_node(attr));
 
-        values.iter().for_each(|value| self.analyze_node(value));
-    }
+        let name = component.name.text(self.content);
+        self.interface
+            .add_component(name, component.name.location());
+        if self.collecting_interface {
+            return;
+        }
 
-    fn validate_text_element_value(&mut self, values: &[AstNode], location: Location) {
-        if values.is_empty() {
+        self.check_component_cycle(name, component.name.location());
+
+        if let Some(path) = self.components.file(name)
+            && !self.templates.contains(&path)
+            && self.global_scope.interface(name).is_none()
+        {
             self.add_diagnostic(
-                location,
-                "Text element has no value to display".into(),
-                DiagnosticSeverity::Warning,
-            )
+                &codes::MISSING_TEMPLATE,
+                component.name.location(),
+                format!(
+                    "no template found for component '{name}', expected it at '{}'",
+                    path.display()
+                ),
+                DiagnosticSeverity::Error,
+            );
+            return;
         }
 
-        for value in values {
-            match value {
-                AstNode::String { .. } => {}
-                AstNode::Primitive { .. } => {}
-                AstNode::Identifier { .. } => {
-                    let name = self.get_node_text(value);
-
-                    if self.symbol_table.lookup_symbol(name).is_some() {
-                        continue;
-                    }
+        let Some(interface) = self.global_scope.interface(name).cloned() else { return };
 
-                    if self.global_scope.lookup_symbol(name).is_some() {
-                        continue;
-                    }
+        let mut provided = vec![];
+        for attribute in component.attributes.items.iter() {
+            let AstNode::Attribute(attribute) = attribute else { continue };
+            // keywords used as names are already reported by the parser
+            let AstNode::Identifier(location) = *attribute.name else { continue };
+            let attribute_name = &self.content[location.to_range()];
+            provided.push(attribute_name);
 
-                    self.add_diagnostic(
-                        location,
-                        format!("reference to undefined identifier '{name}'"),
-                        DiagnosticSeverity::Error,
-                    );
-                    return;
-                }
-                _ => self.add_diagnostic(
-                    location,
-                    "Text element value must be a string literal".into(),
-                    DiagnosticSeverity::Error,
-                ),
+            if interface.input(attribute_name).is_none() {
+                self.add_lint(
+                    &lints::UNKNOWN_ATTRIBUTE,
+                    attribute.name.location(),
+                    format!("component '{name}' has no attribute '{attribute_name}'"),
+                );
             }
         }
-    }
 
-    pub fn analyze_expression(&mut self, expr: &Expr) -> ValueType {
-        match expr {
-            Expr::String(_) => ValueType::String,
-            Expr::Primitive(primitive) => match primitive.value {
-                Primitive::Bool(_) => ValueType::Boolean,
-                Primitive::Int(_) => ValueType::Number,
-                Primitive::Float(_) => ValueType::Number,
-                Primitive::Hex(_) => ValueType::Hex,
-            },
-            Expr::Ident(location) => self.resolve_identifier_type(*location),
-            Expr::List(list) => list
-                .items
-                .iter()
-                .map(|expr| self.analyze_expression(expr))
-                .find(|t| !matches!(t, ValueType::Unknown))
-                .unwrap_or(ValueType::Unknown),
-            Expr::Map(map) => {
-                let (key_type, value_type) = map
-                    .items
-                    .iter()
-                    .map(|(key, val)| (self.analyze_expression(key), self.analyze_expression(val)))
-                    .fold(
-                        (ValueType::Unknown, ValueType::Unknown),
-                        |(acc_k, acc_v), (k, v)| {
-                            (
-                                if matches!(acc_k, ValueType::Unknown) { k } else { acc_k },
-                                if matches!(acc_v, ValueType::Unknown) { v } else { acc_v },
-                            )
-                        },
-                    );
-                ValueType::Map(Box::new(key_type), Box::new(value_type))
-            }
-            Expr::Binary(binary) => {
-                let lhs_type = self.analyze_expression(&binary.lhs);
-                let rhs_type = self.analyze_expression(&binary.rhs);
-                let expected_type = self.get_operator_result_type(binary.op);
-                self.validate_operand_types(&lhs_type, &rhs_type, &expected_type, binary.op);
-                expected_type
-            }
-            Expr::Unary(unary) => {
-                let expr_type = self.analyze_expression(&unary.expr);
-                match unary.op {
-                    Operator::Not => ValueType::Boolean,
-                    Operator::Minus => ValueType::Number,
-                    _ => expr_type,
-                }
-            }
-            Expr::Call(call) => {
-                self.analyze_expression(&call.fun);
-                for arg in call.args.iter() {
-                    self.analyze_expression(arg);
-                }
-                ValueType::Unknown
-            }
-            Expr::ArrayIndex(array_index) => {
-                let lhs_type = self.analyze_expression(&array_index.lhs);
-                self.analyze_expression(&array_index.index);
-                match lhs_type {
-                    ValueType::List(element_type) => *element_type,
-                    _ => ValueType::Unknown,
+        let missing = interface
+            .inputs
+            .iter()
+            .filter(|input| !provided.contains(&input.name.as_str()));
+        for input in missing {
+            let message = match input.value_type {
+                ValueType::Unknown => format!("missing attribute '{}'", input.name),
+                ref value_type => {
+                    format!("missing attribute '{}' of type {value_type}", input.name)
                 }
-            }
-            Expr::Error(_) => ValueType::Unknown,
+            };
+            let diagnostic = SemanticDiagnostic::lint(
+                &lints::MISSING_ATTRIBUTES,
+                component.name.location(),
+                message,
+            )
+            .with_related_in(
+                interface.definition.clone(),
+                input.location,
+                format!("'{}' is read here", input.name),
+            );
+            self.push_diagnostic(diagnostic);
         }
     }
 
-    fn resolve_identifier_type(&mut self, location: Location) -> ValueType {
-        let name = &self.content[location.to_range()];
-
-        if let Some(symbol) = self.symbol_table.lookup_symbol(name) {
-            return match &symbol.symbol_type {
-                SymbolType::Variable(value_type) => value_type.clone(),
-                SymbolType::Element => ValueType::Unknown,
-            };
-        }
+    /// Reports a use of `name` that leads back to the component being analyzed, e.g. `@b` in
+    /// `a.aml` when `b.aml` uses `@a`.
+    fn check_component_cycle(&mut self, name: &str, location: Location) {
+        let Some(current) = self.components.name(&self.file_path) else { return };
+        let Some(path) = self.global_scope.component_path(name, &current) else { return };
 
-        if let Some(symbol) = self.global_scope.lookup_symbol(name) {
-            return match &symbol.symbol_type {
-                SymbolType::Variable(value_type) => value_type.clone(),
-                SymbolType::Element => ValueType::Unknown,
-            };
-        }
+        let cycle = std::iter::once(current.as_str())
+            .chain(std::iter::once(name))
+            .chain(path.iter().map(|(_, component)| component.name.as_str()))
+            .collect::<Vec<_>>()
+            .join(" -> ");
 
-        self.add_diagnostic(
+        let mut diagnostic = SemanticDiagnostic::new(
+            &codes::CYCLIC_COMPONENT,
             location,
-            format!("reference to undefined identifier '{name}'"),
+            format!("cyclic component dependency: {cycle}"),
             DiagnosticSeverity::Error,
         );
-        ValueType::Unknown
+        for (interface, component) in path.iter() {
+            let user = self
+                .components
+                .name(&interface.definition)
+                .unwrap_or_default();
+            diagnostic = diagnostic.with_related_in(
+                interface.definition.clone(),
+                component.location,
+                format!("'{user}' uses '{}' here", component.name),
+            );
+        }
+
+        self.push_diagnostic(diagnostic);
     }
 
-    fn get_operator_result_type(&self, op: Operator) -> ValueType {
-        match op {
-            Operator::Plus | Operator::Minus | Operator::Mul | Operator::Div | Operator::Mod => {
-                ValueType::Number
-            }
-            Operator::EqualEqual
-            | Operator::NotEqual
-            | Operator::GreaterThan
-            | Operator::LessThan
-            | Operator::GreaterThanOrEqual
-            | Operator::LessThanOrEqual
+    fn analyze_container(&mut self, container: &ContainerNode) {
+        self.record_element(container.keyword, &container.attributes);
+        let parent_colors = self.push_colors(&container.attributes);
+        self.validate_attribute_names(&container.attributes);
+        self.validate_container_children(container);
+        container
+            .attributes
+            .items
+            .iter()
+            .for_each(|attr| self.analyze_node(attr));
+
+        let inside_text = std::mem::replace(&mut self.inside_text, false);
+        container
+            .children
+            .iter()
+            .for_each(|child| self.analyze_node(child));
+        self.inside_text = inside_text;
+        self.colors = parent_colors;
+    }
+
+    /// Sets the colours of an element, which its children inherit, returning the colours of its
+    /// parent.
+    fn push_colors(&mut self, attributes: &Attributes) -> Colors {
+        let parent_colors = self.colors;
+        if self.collecting_interface {
+            return parent_colors;
+        }
+
+        for (name, attribute) in self.color_attributes(attributes) {
+            let value = self.constant(&attribute.value).ok();
+            if let Some(value) = &value {
+                self.check_color(&attribute.value, value);
+            }
+
+            // colours can be written quoted as well, `"#rrggbb"`
+            let hex = match value {
+                Some(Value::Hex(hex)) => Some(hex),
+                Some(Value::String(text)) => parse_hex(&text),
+                _ => None,
+            };
+            let color = hex.map(|hex| (hex, attribute.value.location()));
+
+            match name {
+                "foreground" => self.colors.foreground = color,
+                _ => self.colors.background = color,
+            }
+        }
+
+        parent_colors
+    }
+
+    /// The `foreground` and `background` attributes of an element, by name.
+    fn color_attributes<'a>(&self, attributes: &'a Attributes) -> Vec<(&'src str, &'a Attribute)> {
+        attributes
+            .items
+            .iter()
+            .filter_map(|item| {
+                let AstNode::Attribute(attribute) = item else { return None };
+                let AstNode::Identifier(name) = *attribute.name else { return None };
+                let name = &self.content[name.to_range()];
+                matches!(name, "foreground" | "background").then_some((name, attribute))
+            })
+            .collect()
+    }
+
+    /// Checks that a colour is known and that the targeted terminals can show it.
+    fn check_color(&mut self, expr: &Expr, value: &Value) {
+        let location = expr.location();
+        // only literals can be replaced, not the variables holding them
+        let literal = matches!(expr, Expr::Primitive(_) | Expr::String(_));
+
+        let (color, quoted) = match value {
+            Value::Hex(hex) => (*hex, false),
+            Value::String(text) => match parse_hex(text) {
+                Some(hex) => (hex, true),
+                None if is_color_name(text) => return,
+                None => {
+                    let mut diagnostic = SemanticDiagnostic::lint(
+                        &lints::UNKNOWN_COLOR,
+                        location,
+                        format!("unknown colour '{text}'"),
+                    );
+                    if let Some(name) = normalize_color_name(text)
+                        && literal
+                    {
+                        diagnostic = diagnostic.with_fix(location, format!("\"{name}\""));
+                    }
+                    self.push_diagnostic(diagnostic);
+                    return;
+                }
+            },
+            _ => return,
+        };
+
+        let Some(depth) = self.options.color_depth else { return };
+        let Some(nearest) = nearest_color(color, depth) else { return };
+
+        let replacement = match nearest {
+            PaletteColor::Named(name) => format!("\"{name}\""),
+            PaletteColor::Hex(hex) if quoted => format!("\"{hex}\""),
+            PaletteColor::Hex(hex) => hex.to_string(),
+        };
+        let colors = match depth {
+            ColorDepth::Ansi16 => 16,
+            ColorDepth::Xterm256 => 256,
+        };
+        let message =
+            format!("{color} cannot be shown with {colors} colours, the nearest is {replacement}");
+
+        let mut diagnostic = SemanticDiagnostic::lint(&lints::COLOR_DEPTH, location, message);
+        if literal {
+            diagnostic = diagnostic.with_fix(location, replacement);
+        }
+        self.push_diagnostic(diagnostic);
+    }
+
+    fn check_contrast(&mut self, keyword: Location) {
+        let Colors {
+            foreground: Some((foreground, foreground_location)),
+            background: Some((background, background_location)),
+        } = self.colors
+        else {
+            return;
+        };
+
+        let ratio = contrast_ratio(foreground, background);
+        let min_ratio = self.options.min_contrast_ratio;
+        if ratio >= min_ratio {
+            return;
+        }
+
+        let message = format!(
+            "contrast of {foreground} on {background} is {ratio:.1}:1, lower than {min_ratio}:1"
+        );
+        let diagnostic = SemanticDiagnostic::lint(&lints::LOW_CONTRAST, keyword, message)
+            .with_related(foreground_location, "the foreground is set here")
+            .with_related(background_location, "the background is set here");
+        self.push_diagnostic(diagnostic);
+    }
+
+    /// Records an element along with the literal value of its attributes.
+    fn record_element(&mut self, keyword: Location, attributes: &Attributes) {
+        if self.collecting_interface {
+            return;
+        }
+
+        let attributes = attributes
+            .items
+            .iter()
+            .filter_map(|item| {
+                let AstNode::Attribute(attribute) = item else { return None };
+                let AstNode::Identifier(name) = *attribute.name else { return None };
+                let value = match &attribute.value {
+                    Expr::String(location) => Some(self.string_literal(*location).into()),
+                    Expr::Primitive(primitive) => {
+                        Some(self.content[primitive.location.to_range()].into())
+                    }
+                    _ => None,
+                };
+
+                Some(ElementAttribute {
+                    name: self.content[name.to_range()].into(),
+                    value,
+                })
+            })
+            .collect();
+
+        self.elements.push(TemplateElement {
+            tag: self.content[keyword.to_range()].into(),
+            location: keyword,
+            attributes,
+        });
+    }
+
+    fn analyze_span_element(&mut self, values: &[AstNode], attributes: &Attributes) {
+        self.validate_attribute_names(attributes);
+        attributes
+            .items
+            .iter()
+            .for_each(|attr| self.analyze_node(attr));
+
+        values.iter().for_each(|value| self.analyze_node(value));
+    }
+
+    fn validate_text_element_value(&mut self, values: &[AstNode], location: Location) {
+        if values.is_empty() {
+            self.add_lint(
+                &lints::EMPTY_TEXT,
+                location,
+                "Text element has no value to display".into(),
+            )
+        }
+
+        for value in values {
+            match value {
+                AstNode::String { .. } => {}
+                AstNode::Primitive { .. } => {}
+                AstNode::Identifier(location) => _ = self.resolve_identifier_type(*location),
+                // reported as a syntax error
+                AstNode::Error(error) if matches!(error.token, TokenKind::Error(_)) => {}
+                _ => self.add_diagnostic(
+                    &codes::INVALID_TEXT_VALUE,
+                    value.location(),
+                    "Text element value must be a string literal".into(),
+                    DiagnosticSeverity::Error,
+                ),
+            }
+        }
+    }
+
+    fn validate_attribute_names(&mut self, attributes: &Attributes) {
+        let mut seen: HashMap<&str, Location> = HashMap::new();
+
+        for attr in attributes.items.iter() {
+            let AstNode::Attribute(attribute) = attr else { continue };
+            let AstNode::Identifier(location) = *attribute.name else { continue };
+            let name = &self.content[location.to_range()];
+
+            let Some(first) = seen.get(name).copied() else {
+                seen.insert(name, location);
+                continue;
+            };
+
+            let message = format!("duplicate attribute '{name}'");
+            self.push_diagnostic(
+                SemanticDiagnostic::lint(&lints::DUPLICATE_ATTRIBUTE, location, message)
+                    .with_related(first, format!("'{name}' is first set here")),
+            );
+        }
+    }
+
+    fn validate_text_element_children(&mut self, children: &[AstNode]) {
+        for child in children {
+            match child {
+                AstNode::Span(_) => {}
+                AstNode::For(_) => {}
+                AstNode::Declaration(_) => {}
+                AstNode::Error(_) => {}
+                _ => self.add_lint(
+                    &lints::INVALID_TEXT_CHILD,
+                    element_keyword(child),
+                    "'text' only accepts 'span' children".into(),
+                ),
+            }
+        }
+    }
+
+    fn validate_container_children(&mut self, container: &ContainerNode) {
+        let Some(max_children) = max_children(container.kind) else { return };
+
+        // declarations are not elements and errors are already reported by the parser, so neither
+        // counts towards the number of children
+        let surplus = container
+            .children
+            .iter()
+            .filter(|child| !matches!(child, AstNode::Declaration(_) | AstNode::Error(_)))
+            .nth(max_children);
+
+        let Some(surplus) = surplus else { return };
+        let name = &self.content[container.keyword.to_range()];
+        let message = match max_children {
+            0 => format!("'{name}' does not accept children"),
+            _ => format!("'{name}' accepts at most one child"),
+        };
+
+        self.add_diagnostic(
+            &codes::TOO_MANY_CHILDREN,
+            element_keyword(surplus),
+            message,
+            DiagnosticSeverity::Error,
+        );
+    }
+
+    pub fn analyze_expression(&mut self, expr: &Expr) -> ValueType {
+        match expr {
+            Expr::String(_) => ValueType::String,
+            Expr::Primitive(primitive) => match primitive.value {
+                Primitive::Bool(_) => ValueType::Boolean,
+                Primitive::Int(_) => ValueType::Number,
+                Primitive::Float(_) => ValueType::Number,
+                Primitive::Hex(_) => ValueType::Hex,
+            },
+            Expr::Ident(location) => self.resolve_identifier_type(*location),
+            Expr::List(list) => self.analyze_list(list),
+            Expr::Map(map) => self.analyze_map(map),
+            Expr::Binary(binary) if binary.op == Operator::Dot => {
+                // the rhs is a field name, not a reference
+                let lhs_type = self.analyze_expression(&binary.lhs);
+                let Expr::Ident(field) = *binary.rhs else { return ValueType::Unknown };
+                let name = &self.content[field.to_range()];
+                self.resolve_field_type(lhs_type, name, field)
+            }
+            Expr::Binary(binary) if binary.op == Operator::Either => {
+                let lhs_type = self.analyze_expression(&binary.lhs);
+                let rhs_type = self.analyze_expression(&binary.rhs);
+                ValueType::union([lhs_type.without_null(), rhs_type])
+            }
+            Expr::Binary(binary) => {
+                let lhs_type = self.analyze_expression(&binary.lhs);
+                let rhs_type = self.analyze_expression(&binary.rhs);
+                let expected_type = self.get_operator_result_type(binary.op);
+                // `+` also concatenates strings, so it says nothing about its operands
+                if matches!(
+                    binary.op,
+                    Operator::Minus | Operator::Mul | Operator::Div | Operator::Mod
+                ) {
+                    self.infer_input_type(&binary.lhs, ValueType::Number);
+                    self.infer_input_type(&binary.rhs, ValueType::Number);
+                }
+                self.validate_operand_types(&lhs_type, &rhs_type, &expected_type, binary.op);
+                expected_type
+            }
+            Expr::Unary(unary) => {
+                let expr_type = self.analyze_expression(&unary.expr);
+                match unary.op {
+                    Operator::Not => ValueType::Boolean,
+                    Operator::Minus => ValueType::Number,
+                    _ => expr_type,
+                }
+            }
+            Expr::Call(call) => self.analyze_call(call),
+            Expr::ArrayIndex(array_index) => {
+                let lhs_type = self.analyze_expression(&array_index.lhs);
+                self.analyze_expression(&array_index.index);
+                match (lhs_type, &*array_index.index) {
+                    // indexing past the end of a list or a missing key yields null
+                    (ValueType::List(element_type), _) => element_type.optional(),
+                    (ValueType::Map(_, value_type), _) => value_type.optional(),
+                    (record @ ValueType::Record(_), Expr::String(location)) => {
+                        let name = self.string_literal(*location);
+                        self.resolve_field_type(record, &name, *location)
+                    }
+                    _ => ValueType::Unknown,
+                }
+            }
+            Expr::Error(_) => ValueType::Unknown,
+        }
+    }
+
+    fn analyze_list(&mut self, list: &List) -> ValueType {
+        let element_types = list
+            .items
+            .iter()
+            .map(|expr| self.analyze_expression(expr))
+            .collect::<Vec<_>>();
+
+        let known = ValueType::union(
+            element_types
+                .iter()
+                .filter(|element_type| **element_type != ValueType::Unknown)
+                .cloned(),
+        );
+        if let ValueType::Union(members) = &known {
+            let types = members
+                .iter()
+                .map(|member| member.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            self.add_lint(
+                &lints::MIXED_LIST,
+                list.location,
+                format!("list contains elements of different types: {types}"),
+            );
+        }
+
+        ValueType::List(Box::new(ValueType::union(element_types)))
+    }
+
+    /// Map literals with identifier or string keys are typed as records, any other map falls back
+    /// to a single key and value type.
+    fn analyze_map(&mut self, map: &Map) -> ValueType {
+        let is_record = map
+            .items
+            .iter()
+            .all(|(key, _)| matches!(key, Expr::Ident(_) | Expr::String(_)));
+
+        if is_record {
+            let fields = map
+                .items
+                .iter()
+                .map(|(key, value)| {
+                    let name = match *key {
+                        Expr::Ident(location) => Cow::Borrowed(&self.content[location.to_range()]),
+                        _ => self.string_literal(key.location()),
+                    };
+                    (name.to_string(), self.analyze_expression(value))
+                })
+                .collect();
+            return ValueType::Record(fields);
+        }
+
+        let (key_type, value_type) = map
+            .items
+            .iter()
+            .map(|(key, val)| (self.analyze_expression(key), self.analyze_expression(val)))
+            .fold(
+                (ValueType::Unknown, ValueType::Unknown),
+                |(acc_k, acc_v), (k, v)| {
+                    (
+                        if matches!(acc_k, ValueType::Unknown) { k } else { acc_k },
+                        if matches!(acc_v, ValueType::Unknown) { v } else { acc_v },
+                    )
+                },
+            );
+        ValueType::Map(Box::new(key_type), Box::new(value_type))
+    }
+
+    fn resolve_field_type(
+        &mut self,
+        value_type: ValueType,
+        name: &str,
+        location: Location,
+    ) -> ValueType {
+        let ValueType::Record(fields) = &value_type else {
+            return match value_type {
+                ValueType::Map(_, value_type) => *value_type,
+                _ => ValueType::Unknown,
+            };
+        };
+
+        if let Some(field_type) = value_type.field(name) {
+            return field_type.clone();
+        }
+
+        let available = fields
+            .iter()
+            .map(|(field, _)| format!("'{field}'"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.add_diagnostic(
+            &codes::UNKNOWN_FIELD,
+            location,
+            format!("no such field '{name}', available fields are {available}"),
+            DiagnosticSeverity::Error,
+        );
+        ValueType::Unknown
+    }
+
+    /// Checks a call against the function catalog. Functions can be called either as `f(a, b)` or
+    /// as a method `a.f(b)`, where the receiver is passed as the first argument.
+    fn analyze_call(&mut self, call: &Call) -> ValueType {
+        let (name_location, receiver) = match &*call.fun {
+            Expr::Ident(location) => (*location, None),
+            Expr::Binary(Binary {
+                op: Operator::Dot,
+                lhs,
+                rhs,
+                ..
+            }) if matches!(**rhs, Expr::Ident(_)) => (rhs.location(), Some(&**lhs)),
+            fun => {
+                self.analyze_expression(fun);
+                call.args
+                    .iter()
+                    .for_each(|arg| _ = self.analyze_expression(arg));
+                return ValueType::Unknown;
+            }
+        };
+
+        let args = receiver.into_iter().chain(&call.args).collect::<Vec<_>>();
+        let arg_types = args
+            .iter()
+            .map(|arg| self.analyze_expression(arg))
+            .collect::<Vec<_>>();
+
+        let name = &self.content[name_location.to_range()];
+        let Some(function) = functions::lookup_function(name) else {
+            self.add_diagnostic(
+                &codes::UNKNOWN_FUNCTION,
+                name_location,
+                format!("unknown function '{name}'"),
+                DiagnosticSeverity::Error,
+            );
+            return ValueType::Unknown;
+        };
+
+        if !(function.min_args()..=function.max_args()).contains(&args.len()) {
+            let expected = match (function.min_args(), function.max_args()) {
+                (1, 1) => "1 argument".to_string(),
+                (min, max) if min == max => format!("{min} arguments"),
+                (min, max) => format!("{min} to {max} arguments"),
+            };
+            self.add_diagnostic(
+                &codes::ARGUMENT_COUNT,
+                call.location,
+                format!("'{name}' expects {expected}, found {}", args.len()),
+                DiagnosticSeverity::Error,
+            );
+            return function.returns.value_type();
+        }
+
+        for (index, ((arg, arg_type), param)) in
+            args.iter().zip(&arg_types).zip(function.params).enumerate()
+        {
+            if !param.accepts(arg_type) {
+                self.add_diagnostic(
+                    &codes::ARGUMENT_TYPE,
+                    arg.location(),
+                    format!(
+                        "argument {} of '{name}' must be {}, found {arg_type}",
+                        index + 1,
+                        param.describe()
+                    ),
+                    DiagnosticSeverity::Error,
+                );
+            }
+            self.infer_input_type(arg, param.value_type());
+        }
+
+        function.returns.value_type()
+    }
+
+    /// Evaluates a constant expression, reporting evaluation errors such as a division by zero.
+    /// The value of non-literal expressions is kept so it can be shown to the user.
+    pub fn evaluate(&mut self, expr: &Expr) -> Option<Value> {
+        match self.constant(expr) {
+            Ok(value) => {
+                if !matches!(expr, Expr::Primitive(_) | Expr::String(_)) {
+                    self.computed_values.push(ComputedValue {
+                        location: expr.location(),
+                        value: value.clone(),
+                    });
+                }
+                Some(value)
+            }
+            Err(err) => {
+                if let Some(location) = err.location() {
+                    self.add_diagnostic(
+                        &codes::INVALID_EXPRESSION,
+                        location,
+                        err.to_string(),
+                        DiagnosticSeverity::Error,
+                    );
+                }
+                None
+            }
+        }
+    }
+
+    fn resolve_identifier_type(&mut self, location: Location) -> ValueType {
+        let name = &self.content[location.to_range()];
+        let mut reference = Reference {
+            name: name.into(),
+            location,
+            scope_id: self.symbol_table.current_scope_id(),
+            target: ReferenceTarget::Unresolved,
+        };
+
+        if let Some(symbol) = self.symbol_table.lookup_symbol(name) {
+            let value_type = symbol.symbol_type.value_type();
+            reference.target = ReferenceTarget::Local(symbol.location);
+            self.symbol_table.add_reference(reference);
+            return value_type;
+        }
+
+        if let Some(symbol) = self.global_scope.lookup_symbol(name) {
+            let value_type = symbol.symbol_type.value_type();
+            reference.target = ReferenceTarget::Global {
+                definition: symbol.definition.clone(),
+            };
+            self.symbol_table.add_reference(reference);
+            return value_type;
+        }
+
+        self.symbol_table.add_reference(reference);
+        self.interface.add_input(name, location);
+        if !self.is_component {
+            self.add_diagnostic(
+                &codes::UNDEFINED_IDENTIFIER,
+                location,
+                format!("reference to undefined identifier '{name}'"),
+                DiagnosticSeverity::Error,
+            );
+        }
+        ValueType::Unknown
+    }
+
+    /// A template is a component when it is registered as one, or when another template uses it.
+    fn is_component(&self) -> bool {
+        if self
+            .components
+            .registered
+            .values()
+            .any(|file| *file == self.file_path)
+        {
+            return true;
+        }
+
+        let Some(name) = self.components.name(&self.file_path) else { return false };
+        self.global_scope
+            .interfaces
+            .values()
+            .any(|interface| interface.components.iter().any(|used| used.name == name))
+    }
+
+    /// Narrows the type of a component input from how it is used, e.g. `to_upper(label)` expects
+    /// `label` to be a string.
+    fn infer_input_type(&mut self, expr: &Expr, value_type: ValueType) {
+        if let Expr::Ident(location) = expr {
+            let name = &self.content[location.to_range()];
+            self.interface.infer_type(name, value_type);
+        }
+    }
+
+    fn get_operator_result_type(&self, op: Operator) -> ValueType {
+        match op {
+            Operator::Plus | Operator::Minus | Operator::Mul | Operator::Div | Operator::Mod => {
+                ValueType::Number
+            }
+            Operator::EqualEqual
+            | Operator::NotEqual
+            | Operator::GreaterThan
+            | Operator::LessThan
+            | Operator::GreaterThanOrEqual
+            | Operator::LessThanOrEqual
             | Operator::And
             | Operator::Or => ValueType::Boolean,
             _ => ValueType::Unknown,
@@ -297,48 +1401,959 @@ impl<'src> SemanticAnalyzer<'src> {
         }
     }
 
-    fn get_node_text(&self, node: &AstNode) -> &'src str {
-        match node {
-            AstNode::Identifier(location) => &self.content[location.to_range()],
-            AstNode::String(location) => &self.content[location.to_range()],
-            _ => panic!("Unsupported node type for text extraction"),
-        }
+    /// Evaluates an expression without reporting anything about it.
+    fn constant(&self, expr: &Expr) -> Result<Value, EvalError> {
+        let symbol_table = &self.symbol_table;
+        let global_scope = self.global_scope;
+        let lookup = |name: &str| match symbol_table.lookup_symbol(name) {
+            Some(symbol) => symbol.value.clone(),
+            None => global_scope.lookup_symbol(name)?.value.clone(),
+        };
+
+        Evaluator::new(self.content, lookup).evaluate(expr)
+    }
+
+    fn string_literal(&self, location: Location) -> Cow<'src, str> {
+        evaluator::string_literal(self.content, location)
     }
 
     fn add_diagnostic(
         &mut self,
+        code: &'static Code,
         location: Location,
         message: String,
         severity: DiagnosticSeverity,
     ) {
-        self.diagnostics.push(SemanticDiagnostic {
-            location,
-            message,
-            severity,
-        });
+        self.push_diagnostic(SemanticDiagnostic::new(code, location, message, severity));
+    }
+
+    fn add_lint(&mut self, lint: &'static Lint, location: Location, message: String) {
+        self.push_diagnostic(SemanticDiagnostic::lint(lint, location, message));
+    }
+
+    /// Pushes a diagnostic, with the severity of its lint or not at all if the lint is allowed.
+    fn push_diagnostic(&mut self, mut diagnostic: SemanticDiagnostic) {
+        if self.collecting_interface {
+            return;
+        }
+
+        if let Some(lint) = diagnostic.lint.and_then(lints::find) {
+            let Some(severity) = self.options.lints.severity(lint) else { return };
+            if self.suppressions.allows(lint, diagnostic.location) {
+                return;
+            }
+            diagnostic.severity = severity;
+        }
+
+        self.diagnostics.push(diagnostic);
     }
 }
 
-struct GlobalCollector<'src> {
-    file_path: PathBuf,
-    content: &'src str,
-    analyzer: &'src mut SemanticAnalyzer<'src>,
+/// Maximum number of children a container accepts, or `None` if there is no limit.
+fn max_children(kind: Container) -> Option<usize> {
+    match kind {
+        Container::Border
+        | Container::Padding
+        | Container::Expand
+        | Container::Position
+        | Container::Alignment
+        | Container::Container => Some(1),
+        Container::Canvas | Container::Spacer => Some(0),
+        Container::VStack
+        | Container::HStack
+        | Container::ZStack
+        | Container::Row
+        | Container::Column
+        | Container::Overflow => None,
+    }
 }
 
-impl<'src> AstVisitor<'src> for GlobalCollector<'src> {
-    fn visit_globals(&mut self, decl: &Declaration, _: &AstNode) {
-        assert!(decl.is_global());
+/// Location of the keyword of an element, so diagnostics don't cover the whole subtree.
+fn element_keyword(node: &AstNode) -> Location {
+    match node {
+        AstNode::Container(container) => container.keyword,
+        AstNode::Text(text) => text.keyword,
+        AstNode::Span(span) => span.keyword,
+        AstNode::For(for_loop) => for_loop.keyword,
+        AstNode::Component(component) => component.name.location(),
+        node => node.location(),
+    }
+}
 
-        let name = decl.name.text(self.content).into();
-        let value_type = self.analyzer.analyze_expression(&decl.value);
-        let symbol_type = SymbolType::Variable(value_type);
-        let location = decl.location;
+#[derive(Default)]
+struct GlobalCollector<'ast> {
+    declarations: Vec<&'ast Declaration>,
+}
 
-        self.analyzer.global_scope.declare_global(GlobalSymbol {
-            name,
-            location,
-            symbol_type,
-            definition: self.file_path.clone(),
+impl<'ast> AstVisitor<'ast> for GlobalCollector<'ast> {
+    fn visit_globals(&mut self, decl: &'ast Declaration, _: &'ast AstNode) {
+        self.declarations.push(decl);
+    }
+
+    fn visit_container(&mut self, container: &'ast ContainerNode, _: &'ast AstNode) {
+        container
+            .children
+            .iter()
+            .for_each(|child| child.accept(self));
+    }
+
+    fn visit_text(&mut self, text: &'ast Text, _: &'ast AstNode) {
+        text.children.iter().for_each(|child| child.accept(self));
+    }
+
+    fn visit_for(&mut self, for_loop: &'ast For, _: &'ast AstNode) {
+        for_loop
+            .children
+            .iter()
+            .for_each(|child| child.accept(self));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aml_syntax::Parser;
+    use aml_token::{Lexer, Tokens};
+
+    use super::*;
+    use crate::lints::LintLevel;
+
+    fn parse(template: &str) -> Ast {
+        let tokens = Lexer::new(template).collect();
+        let tokens = Tokens::new(tokens, template.len());
+        Parser::new(tokens).parse()
+    }
+
+    /// Analyzes a template with the analyzer `configure` returns, e.g. one with a state.
+    fn analyze_with<'src>(
+        template: &'src str,
+        global_scope: &'src GlobalScope,
+        configure: impl FnOnce(SemanticAnalyzer<'src>) -> SemanticAnalyzer<'src>,
+    ) -> SemanticInfo {
+        configure(SemanticAnalyzer::new(template, global_scope)).analyze(&parse(template))
+    }
+
+    fn analyze(template: &str) -> Vec<SemanticDiagnostic> {
+        analyze_with(template, &GlobalScope::new(), |analyzer| analyzer).diagnostics
+    }
+
+    /// The text each diagnostic is reported on, along with its message.
+    fn messages_of<'src>(
+        template: &'src str,
+        diagnostics: &[SemanticDiagnostic],
+    ) -> Vec<(&'src str, String)> {
+        diagnostics
+            .iter()
+            .map(|diag| (&template[diag.location.to_range()], diag.message.clone()))
+            .collect()
+    }
+
+    fn messages(template: &str) -> Vec<(&str, String)> {
+        messages_of(template, &analyze(template))
+    }
+
+    #[test]
+    fn test_single_child_container_surplus() {
+        let template = r#"
+border
+    text "one"
+    text "two"
+    text "three"
+"#;
+        assert_eq!(
+            messages(template),
+            vec![("text", "'border' accepts at most one child".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_single_child_container_errors() {
+        let template = "border\n    text \"one\"\n";
+        let mut ast = parse(template);
+        let AstNode::Container(border) = &mut ast.nodes[0] else { unreachable!() };
+        // a child the parser could not make sense of
+        border.children.insert(
+            0,
+            AstNode::Error(ErrorNode {
+                token: TokenKind::Equal,
+                location: Location::new(11, 11),
+            }),
+        );
+
+        let diagnostics = SemanticAnalyzer::new(template, &GlobalScope::new())
+            .analyze(&ast)
+            .diagnostics;
+        assert!(
+            diagnostics
+                .iter()
+                .all(|diag| diag.code != &codes::TOO_MANY_CHILDREN)
+        );
+    }
+
+    #[test]
+    fn test_container_without_children() {
+        let template = r#"
+vstack
+    canvas
+        text "a"
+    spacer
+"#;
+        assert_eq!(
+            messages(template),
+            vec![("text", "'canvas' does not accept children".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_text_only_accepts_spans() {
+        let template = r#"
+text "a"
+    span "b"
+    border
+        text "c"
+"#;
+        assert_eq!(
+            messages(template),
+            vec![("border", "'text' only accepts 'span' children".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_attributes() {
+        let template = r#"
+vstack [width: 10, height: 2, width: 20]
+    text [foreground: #ff0000] "a"
+"#;
+        let diagnostics = analyze(template);
+        assert_eq!(diagnostics.len(), 1);
+
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.message, "duplicate attribute 'width'");
+        assert_eq!(diagnostic.location, Location::new(31, 36));
+        assert_eq!(diagnostic.related.len(), 1);
+        assert_eq!(diagnostic.related[0].location, Location::new(9, 14));
+    }
+
+    #[test]
+    fn test_unused_locals() {
+        let template = r#"
+let used = "a"
+let unused = "b"
+let _ignored = "c"
+for item in [1, 2]
+    text used
+for other in [1, 2]
+    text [width: other] "d"
+let passed = "e"
+@card [title: passed]
+"#;
+        let diagnostics = analyze(template);
+        let unused = diagnostics
+            .iter()
+            .filter(|diag| diag.tags.contains(&DiagnosticTag::Unnecessary))
+            .map(|diag| diag.message.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            unused,
+            vec![
+                "unused local variable 'unused'",
+                "unused loop binding 'item'"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shadowing() {
+        let template = r#"
+let title = "a"
+let accent = "b"
+for title in [1, 2]
+    text [foreground: accent] title
+"#;
+        let mut global_scope = GlobalScope::new();
+        global_scope.declare_global(GlobalSymbol {
+            name: "accent".into(),
+            location: Location::new(0, 20),
+            symbol_type: SymbolType::Variable(ValueType::Hex),
+            definition: PathBuf::from("theme.aml"),
+            value: None,
         });
+
+        let options = AnalyzerOptions {
+            lints: LintLevels::default().with(&lints::SHADOWING, LintLevel::Warn),
+            ..Default::default()
+        };
+        let diagnostics = analyze_with(template, &global_scope, |analyzer| {
+            analyzer.with_options(options)
+        })
+        .diagnostics;
+
+        let shadowing = diagnostics
+            .iter()
+            .filter(|diag| diag.message.contains("shadows"))
+            .map(|diag| {
+                let related = diag.related.first().map(|related| related.file.clone());
+                (&template[diag.location.to_range()], related)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            shadowing,
+            vec![
+                ("accent", Some(Some(PathBuf::from("theme.aml")))),
+                ("title", Some(None)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_shadowing_by_default() {
+        let template = r#"
+let title = "a"
+for title in [1, 2]
+    text title
+"#;
+        let diagnostics = analyze(template);
+        assert!(
+            !diagnostics
+                .iter()
+                .any(|diag| diag.message.contains("shadows"))
+        );
+    }
+
+    #[test]
+    fn test_conflicting_globals() {
+        let template = r#"
+global accent = #ff0000
+global border_color = #00ff00
+"#;
+        let mut global_scope = GlobalScope::new();
+        for (definition, location) in [("index.aml", (1, 24)), ("theme.aml", (0, 22))] {
+            global_scope.declare_global(GlobalSymbol {
+                name: "accent".into(),
+                location: location.into(),
+                symbol_type: SymbolType::Variable(ValueType::Hex),
+                definition: PathBuf::from(definition),
+                value: None,
+            });
+        }
+
+        let diagnostics = analyze_with(template, &global_scope, |analyzer| {
+            analyzer.with_file_path(PathBuf::from("index.aml"))
+        })
+        .diagnostics;
+
+        assert_eq!(
+            messages_of(template, &diagnostics),
+            vec![(
+                "accent",
+                "conflicting declarations of global 'accent'".to_string()
+            )]
+        );
+        assert_eq!(
+            diagnostics[0].related[0].file,
+            Some(PathBuf::from("theme.aml"))
+        );
+    }
+
+    #[test]
+    fn test_conflicting_globals_values() {
+        let template = r#"
+global accent = #ff0000
+global accent = #00ff00
+global spacing = 2
+"#;
+        let mut global_scope = GlobalScope::new();
+        for (definition, text) in [("index.aml", template), ("theme.aml", "global spacing = 2")] {
+            let empty = GlobalScope::new();
+            let globals = SemanticAnalyzer::new(text, &empty)
+                .with_file_path(PathBuf::from(definition))
+                .collect_globals(&parse(text));
+            for global in globals {
+                global_scope.declare_global(global);
+            }
+        }
+
+        let diagnostics = analyze_with(template, &global_scope, |analyzer| {
+            analyzer.with_file_path(PathBuf::from("index.aml"))
+        })
+        .diagnostics;
+
+        // the duplicates in the same template conflict with each other, not with themselves, while
+        // the same spacing in another template does not conflict at all
+        let related = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let related = &diagnostic.related;
+                (
+                    &template[diagnostic.location.to_range()],
+                    related.len(),
+                    related.first().map(|related| related.location),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            related,
+            vec![
+                ("accent", 1, Some(Location::new(25, 48))),
+                ("accent", 1, Some(Location::new(1, 24))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_undefined_identifiers_in_text_values() {
+        let template = r#"
+let known = "a"
+text known first "b" second
+    span "c"
+"#;
+        assert_eq!(
+            messages(template),
+            vec![
+                (
+                    "first",
+                    "reference to undefined identifier 'first'".to_string()
+                ),
+                (
+                    "second",
+                    "reference to undefined identifier 'second'".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_computed_values() {
+        let template = r#"
+let gap = 2
+let none = gap - 2
+vstack [width: 5 + gap, height: 10 / none]
+    text [padding: gap] "a"
+"#;
+        let info = analyze_with(template, &GlobalScope::new(), |analyzer| analyzer);
+
+        let width = template.find("5 + gap").unwrap();
+        let computed = info.computed_value_at(width).unwrap();
+        assert_eq!(computed.value, Value::Int(7));
+
+        let padding = template.find("gap]").unwrap();
+        let computed = info.computed_value_at(padding).unwrap();
+        assert_eq!(computed.value, Value::Int(2));
+
+        assert_eq!(
+            messages_of(template, &info.diagnostics),
+            vec![("10 / none", "division by zero".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_function_calls() {
+        let template = r#"
+let name = "anathema"
+let size = width(name) + 1
+vstack [width: size, height: round(1, 2, 3), title: to_upper(name)]
+    text [title: name.truncate(3), id: name.to_lower().width()] "a"
+    text [title: to_title(name), id: join(name, ","), visible: contains(name, size)] "b"
+    text [title: to_int(name).truncate(2)] "c"
+"#;
+        assert_eq!(
+            messages(template),
+            vec![
+                (
+                    "round(1, 2, 3)",
+                    "'round' expects 1 to 2 arguments, found 3".into()
+                ),
+                ("to_title", "unknown function 'to_title'".into()),
+                (
+                    "name",
+                    "argument 1 of 'join' must be a list, found string".into()
+                ),
+                (
+                    "to_int(name)",
+                    "argument 1 of 'truncate' must be a string, found number".into()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_record_fields() {
+        let template = r#"
+let cfg = {width: 10, "title": "x"}
+let lookup = {1: "one"}
+vstack [width: cfg.width, title: cfg.titel.to_upper(), id: cfg["heigth"], x: lookup[1]]
+    text [title: cfg.title.truncate(cfg.width)] "a"
+    text [title: cfg.width.to_upper()] "b"
+"#;
+        assert_eq!(
+            messages(template),
+            vec![
+                (
+                    "titel",
+                    "no such field 'titel', available fields are 'width', 'title'".into()
+                ),
+                (
+                    "\"heigth\"",
+                    "no such field 'heigth', available fields are 'width', 'title'".into()
+                ),
+                (
+                    "cfg.width",
+                    "argument 1 of 'to_upper' must be a string, found number".into()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_state_fields() {
+        let template = r#"
+let title = user.name.to_upper()
+for item in items
+    text [title: item.label, id: item.lable] title
+"#;
+        let schema = StateSchema::parse("[user]\nname = \"string\"\n[[items]]\nlabel = \"string\"");
+        let info = analyze_with(template, &GlobalScope::new(), |analyzer| {
+            analyzer.with_state(&schema.unwrap())
+        });
+
+        assert_eq!(
+            messages_of(template, &info.diagnostics),
+            vec![(
+                "lable",
+                "no such field 'lable', available fields are 'label'".to_string()
+            )]
+        );
+
+        let symbol = info.symbol_table.lookup_symbol("items").unwrap();
+        assert_eq!(
+            symbol.symbol_type.value_type().to_string(),
+            "list<{label: string}>"
+        );
+        assert_eq!(
+            info.symbol_table.find_references("title")[0].symbol_type,
+            SymbolType::Variable(ValueType::String)
+        );
+
+        let fields = info
+            .symbol_table
+            .state_fields()
+            .into_iter()
+            .map(|symbol| symbol.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(fields, vec!["items", "user"]);
+    }
+
+    #[test]
+    fn test_union_types() {
+        let template = r#"
+let names = ["a", "b"]
+let mixed = [1, "two", 3]
+let first = names[0]
+let fallback = names[5] ? 0
+vstack [title: first.to_upper(), id: fallback.to_upper(), width: mixed]
+"#;
+        let info = analyze_with(template, &GlobalScope::new(), |analyzer| analyzer);
+
+        let type_of = |name: &str| {
+            info.symbol_table
+                .lookup_symbol(name)
+                .unwrap()
+                .symbol_type
+                .value_type()
+                .to_string()
+        };
+        assert_eq!(type_of("first"), "string | null");
+        assert_eq!(type_of("fallback"), "string | number");
+        assert_eq!(type_of("mixed"), "list<number | string>");
+
+        assert_eq!(
+            messages_of(template, &info.diagnostics),
+            vec![
+                (
+                    "[1, \"two\", 3]",
+                    "list contains elements of different types: number, string".into()
+                ),
+                (
+                    "fallback",
+                    "argument 1 of 'to_upper' must be a string, found string | number".into()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_component_inputs_are_not_undefined() {
+        let index = "@button [label: \"ok\"]";
+        let mut global_scope = GlobalScope::new();
+        let interface = analyze_with(index, &global_scope, |analyzer| {
+            analyzer.with_file_path(PathBuf::from("templates/index.aml"))
+        })
+        .interface;
+        global_scope.declare_interface("index", interface);
+
+        let button = "text label";
+        let info = analyze_with(button, &global_scope, |analyzer| {
+            analyzer.with_file_path(PathBuf::from("templates/button.aml"))
+        });
+        assert_eq!(info.diagnostics, vec![]);
+        assert_eq!(info.interface.inputs[0].name, "label");
+
+        // a template nothing uses is not a component
+        let info = analyze_with(button, &global_scope, |analyzer| {
+            analyzer.with_file_path(PathBuf::from("templates/other.aml"))
+        });
+        assert_eq!(
+            messages_of(button, &info.diagnostics),
+            vec![(
+                "label",
+                "reference to undefined identifier 'label'".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_keywords_as_names() {
+        let mut global_scope = GlobalScope::new();
+        let card = "text value";
+        let interface = analyze_with(card, &global_scope, |analyzer| {
+            analyzer.with_file_path(PathBuf::from("templates/card.aml"))
+        })
+        .interface;
+        global_scope.declare_interface("card", interface);
+
+        let template = "@card [default: 1, value: 2]\nlet default = 3";
+        let info = analyze_with(template, &global_scope, |analyzer| analyzer);
+        assert!(
+            info.diagnostics
+                .iter()
+                .all(|diag| diag.code != &codes::UNKNOWN_ATTRIBUTE)
+        );
+    }
+
+    #[test]
+    fn test_component_interface() {
+        let button = r#"
+border
+    vstack [title: to_upper(label), width: size * 2]
+        for item in items
+            text [id: item] "x"
+        $children
+"#;
+        let mut global_scope = GlobalScope::new();
+        let interface = analyze_with(button, &global_scope, |analyzer| {
+            analyzer.with_file_path(PathBuf::from("templates/button.aml"))
+        })
+        .interface;
+
+        let inputs = interface
+            .inputs
+            .iter()
+            .map(|input| (input.name.as_str(), input.value_type.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            inputs,
+            vec![
+                ("label", "string".into()),
+                ("size", "number".into()),
+                ("items", "list<unknown>".into())
+            ]
+        );
+        assert_eq!(interface.slots, vec!["children".to_string()]);
+        global_scope.declare_interface("button", interface);
+
+        let template = r#"
+@button [label: "ok", lable: "typo", items: []]
+"#;
+        let diagnostics = analyze_with(template, &global_scope, |analyzer| {
+            analyzer.with_options(AnalyzerOptions {
+                lints: LintLevels::default().with(&lints::MISSING_ATTRIBUTES, LintLevel::Warn),
+                ..Default::default()
+            })
+        })
+        .diagnostics;
+
+        assert_eq!(
+            messages_of(template, &diagnostics),
+            vec![
+                (
+                    "lable",
+                    "component 'button' has no attribute 'lable'".into()
+                ),
+                ("button", "missing attribute 'size' of type number".into()),
+            ]
+        );
+        assert_eq!(
+            diagnostics[1].related[0].file,
+            Some(PathBuf::from("templates/button.aml"))
+        );
+    }
+
+    #[test]
+    fn test_cyclic_components() {
+        let mut global_scope = GlobalScope::new();
+        let mut analyze_component = |name: &str, template: &str| {
+            let info = analyze_with(template, &global_scope, |analyzer| {
+                analyzer.with_file_path(PathBuf::from(format!("templates/{name}.aml")))
+            });
+            global_scope.declare_interface(name, info.interface);
+            info.diagnostics
+        };
+
+        analyze_component("a", "@b");
+        analyze_component("b", "vstack\n    @c\n    @a");
+        analyze_component("c", "text \"leaf\"");
+
+        let diagnostics = analyze_component("a", "@b");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "cyclic component dependency: a -> b -> a"
+        );
+        assert_eq!(diagnostics[0].location, Location::new(1, 2));
+        assert_eq!(diagnostics[0].related.len(), 1);
+        assert_eq!(diagnostics[0].related[0].message, "'b' uses 'a' here");
+        assert_eq!(
+            diagnostics[0].related[0].file,
+            Some(PathBuf::from("templates/b.aml"))
+        );
+
+        let diagnostics = analyze_component("c", "@c");
+        assert_eq!(
+            diagnostics[0].message,
+            "cyclic component dependency: c -> c"
+        );
+    }
+
+    #[test]
+    fn test_missing_component_template() {
+        let templates_dir = PathBuf::from("templates");
+        let template = "vstack\n    @button\n    @missing";
+        let diagnostics = analyze_with(template, &GlobalScope::new(), |analyzer| {
+            analyzer
+                .with_templates_dir(templates_dir.clone())
+                .with_templates(vec![templates_dir.join("button.aml")])
+        })
+        .diagnostics;
+
+        let expected = templates_dir.join("missing.aml");
+        assert_eq!(
+            messages_of(template, &diagnostics),
+            vec![(
+                "missing",
+                format!(
+                    "no template found for component 'missing', expected it at '{}'",
+                    expected.display()
+                )
+            )]
+        );
+    }
+
+    #[test]
+    fn test_references() {
+        let template = r#"
+let size = 1
+vstack [width: size, height: theme]
+    for size in [size]
+        text [width: size, height: missing] "x"
+"#;
+        let mut global_scope = GlobalScope::new();
+        global_scope.declare_global(GlobalSymbol {
+            name: "theme".into(),
+            location: Location::new(0, 10),
+            symbol_type: SymbolType::Variable(ValueType::Hex),
+            definition: PathBuf::from("theme.aml"),
+            value: None,
+        });
+        let info = analyze_with(template, &global_scope, |analyzer| analyzer);
+        let symbol_table = &info.symbol_table;
+
+        let references = symbol_table
+            .references()
+            .iter()
+            .map(|reference| (&template[reference.location.to_range()], &reference.target))
+            .collect::<Vec<_>>();
+        let local = ReferenceTarget::Local(Location::new(1, 13));
+        let binding_start = template.find("for size").unwrap() + 4;
+        let binding = ReferenceTarget::Local(Location::new(binding_start, binding_start + 4));
+        let global = ReferenceTarget::Global {
+            definition: PathBuf::from("theme.aml"),
+        };
+        assert_eq!(
+            references,
+            vec![
+                ("size", &local),
+                ("theme", &global),
+                ("size", &local),
+                ("size", &binding),
+                ("missing", &ReferenceTarget::Unresolved),
+            ]
+        );
+
+        let declaration = symbol_table.lookup_symbol("size").unwrap();
+        assert_eq!(symbol_table.references_to(declaration).count(), 2);
+
+        let offset = template.rfind("size,").unwrap();
+        let reference = symbol_table.reference_at(offset).unwrap();
+        let symbol = symbol_table.resolve(reference).unwrap();
+        assert_eq!(
+            symbol.symbol_type,
+            SymbolType::LoopBinding(ValueType::Number)
+        );
+        // the text element opens a scope of its own inside the loop
+        let scope = symbol_table.get_scope(reference.scope_id).unwrap();
+        assert_eq!(scope.parent, Some(symbol.scope_id));
+    }
+
+    #[test]
+    fn test_span_outside_text() {
+        let template = r#"
+vstack
+    span "a"
+    text "b"
+        span "c"
+"#;
+        assert_eq!(
+            messages(template),
+            vec![("span", "'span' must be inside a 'text' element".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_low_contrast() {
+        let template = r#"
+let dim = #444444
+vstack [background: #000000]
+    text [foreground: dim] "dim"
+    text [foreground: #ffffff] "bright"
+        span [background: #eeeeee] "light"
+        span "inherited"
+"#;
+        assert_eq!(
+            messages(template),
+            vec![
+                (
+                    "text",
+                    "contrast of #444444 on #000000 is 2.2:1, lower than 4.5:1".to_string()
+                ),
+                (
+                    "span",
+                    "contrast of #ffffff on #eeeeee is 1.2:1, lower than 4.5:1".to_string()
+                ),
+            ]
+        );
+
+        let diagnostics = analyze(template);
+        let related = diagnostics[0]
+            .related
+            .iter()
+            .map(|related| &template[related.location.to_range()])
+            .collect::<Vec<_>>();
+        assert_eq!(related, vec!["dim", "#000000"]);
+    }
+
+    #[test]
+    fn test_low_contrast_with_quoted_colors() {
+        let template = r##"
+vstack [background: "#000000"]
+    text [foreground: "#444444"] "dim"
+    text [foreground: "#ffffff"] "bright"
+        span [background: "#eeeeee"] "light"
+"##;
+        assert_eq!(
+            messages(template),
+            vec![
+                (
+                    "text",
+                    "contrast of #444444 on #000000 is 2.2:1, lower than 4.5:1".to_string()
+                ),
+                (
+                    "span",
+                    "contrast of #ffffff on #eeeeee is 1.2:1, lower than 4.5:1".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_color_depth() {
+        let template = r##"
+let accent = #ff1010
+text [foreground: #ff1010, background: "#101010"] "a"
+text [foreground: accent] "b"
+text [foreground: "Dark Red", background: "blue"] "c"
+"##;
+        let global_scope = GlobalScope::new();
+        let analyze = |color_depth| {
+            analyze_with(template, &global_scope, |analyzer| {
+                analyzer.with_options(AnalyzerOptions {
+                    color_depth,
+                    ..Default::default()
+                })
+            })
+            .diagnostics
+        };
+        let fixes = |diagnostics: &[SemanticDiagnostic]| {
+            diagnostics
+                .iter()
+                .map(|diagnostic| {
+                    let fixes = diagnostic
+                        .fixes
+                        .iter()
+                        .map(|fix| (&template[fix.location.to_range()], fix.replacement.clone()))
+                        .collect::<Vec<_>>();
+                    (diagnostic.message.clone(), fixes)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let diagnostics = analyze(None);
+        assert_eq!(
+            fixes(&diagnostics),
+            vec![(
+                "unknown colour 'Dark Red'".to_string(),
+                vec![("\"Dark Red\"", "\"dark_red\"".to_string())]
+            )]
+        );
+
+        let diagnostics = analyze(Some(ColorDepth::Ansi16));
+        assert_eq!(
+            fixes(&diagnostics),
+            vec![
+                (
+                    "#ff1010 cannot be shown with 16 colours, the nearest is \"red\"".to_string(),
+                    vec![("#ff1010", "\"red\"".to_string())]
+                ),
+                (
+                    "#101010 cannot be shown with 16 colours, the nearest is \"black\"".to_string(),
+                    vec![("\"#101010\"", "\"black\"".to_string())]
+                ),
+                // the fix belongs on the declaration, not on the variable
+                (
+                    "#ff1010 cannot be shown with 16 colours, the nearest is \"red\"".to_string(),
+                    vec![]
+                ),
+                (
+                    "unknown colour 'Dark Red'".to_string(),
+                    vec![("\"Dark Red\"", "\"dark_red\"".to_string())]
+                ),
+            ]
+        );
+
+        let diagnostics = analyze(Some(ColorDepth::Xterm256));
+        assert_eq!(
+            fixes(&diagnostics)[..2],
+            [
+                (
+                    "#ff1010 cannot be shown with 256 colours, the nearest is #ff0000".to_string(),
+                    vec![("#ff1010", "#ff0000".to_string())]
+                ),
+                (
+                    "#101010 cannot be shown with 256 colours, the nearest is \"#121212\""
+                        .to_string(),
+                    vec![("\"#101010\"", "\"#121212\"".to_string())]
+                ),
+            ]
+        );
     }
 }
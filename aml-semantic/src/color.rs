@@ -0,0 +1,179 @@
+//! Colour arithmetic for the lints on the colours of text.
+
+use aml_config::ColorDepth;
+use aml_token::Hex;
+
+/// The 16 ANSI colours by their name in Anathema, in the order of their index, with the RGB values
+/// xterm shows them with.
+pub const ANSI_COLORS: [(&str, (u8, u8, u8)); 16] = [
+    ("black", (0x00, 0x00, 0x00)),
+    ("dark_red", (0xcd, 0x00, 0x00)),
+    ("dark_green", (0x00, 0xcd, 0x00)),
+    ("dark_yellow", (0xcd, 0xcd, 0x00)),
+    ("dark_blue", (0x00, 0x00, 0xee)),
+    ("dark_magenta", (0xcd, 0x00, 0xcd)),
+    ("dark_cyan", (0x00, 0xcd, 0xcd)),
+    ("grey", (0xe5, 0xe5, 0xe5)),
+    ("dark_grey", (0x7f, 0x7f, 0x7f)),
+    ("red", (0xff, 0x00, 0x00)),
+    ("green", (0x00, 0xff, 0x00)),
+    ("yellow", (0xff, 0xff, 0x00)),
+    ("blue", (0x5c, 0x5c, 0xff)),
+    ("magenta", (0xff, 0x00, 0xff)),
+    ("cyan", (0x00, 0xff, 0xff)),
+    ("white", (0xff, 0xff, 0xff)),
+];
+
+/// The levels of each channel in the 6x6x6 colour cube of xterm.
+const CUBE_LEVELS: [u8; 6] = [0x00, 0x5f, 0x87, 0xaf, 0xd7, 0xff];
+
+/// A colour of a palette, either an ANSI colour by name or one of the 256 colours of xterm.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaletteColor {
+    Named(&'static str),
+    Hex(Hex),
+}
+
+/// Whether Anathema knows a colour by this name.
+pub fn is_color_name(name: &str) -> bool {
+    name == "reset" || ANSI_COLORS.iter().any(|(ansi, _)| *ansi == name)
+}
+
+/// The name of a colour as Anathema spells it, e.g. `dark_red` for `DarkRed` or `dark-red`.
+pub fn normalize_color_name(name: &str) -> Option<&'static str> {
+    let mut normalized = String::with_capacity(name.len());
+    for (index, char) in name.trim().char_indices() {
+        match char {
+            '-' | ' ' => normalized.push('_'),
+            char if char.is_uppercase() => {
+                if index > 0 && !normalized.ends_with('_') {
+                    normalized.push('_');
+                }
+                normalized.extend(char.to_lowercase());
+            }
+            char => normalized.push(char),
+        }
+    }
+
+    let normalized = normalized.replace("gray", "grey");
+    ANSI_COLORS
+        .iter()
+        .map(|(name, _)| *name)
+        .chain(["reset"])
+        .find(|name| *name == normalized)
+}
+
+/// Parses a `#rrggbb` string.
+pub fn parse_hex(text: &str) -> Option<Hex> {
+    let digits = text.strip_prefix('#')?;
+    if digits.len() != 6 {
+        return None;
+    }
+
+    let channel = |index: usize| u8::from_str_radix(digits.get(index..index + 2)?, 16).ok();
+    Some(Hex::from((channel(0)?, channel(2)?, channel(4)?)))
+}
+
+/// The colour of the palette of a depth that is closest to the given one, `None` if the palette
+/// has the colour itself.
+pub fn nearest_color(color: Hex, depth: ColorDepth) -> Option<PaletteColor> {
+    let palette: Vec<(PaletteColor, (u8, u8, u8))> = match depth {
+        ColorDepth::Ansi16 => ANSI_COLORS
+            .iter()
+            .map(|(name, rgb)| (PaletteColor::Named(name), *rgb))
+            .collect(),
+        ColorDepth::Xterm256 => xterm_palette()
+            .map(|rgb| (PaletteColor::Hex(Hex::from(rgb)), rgb))
+            .collect(),
+    };
+
+    let rgb = color.rgb();
+    let (nearest, nearest_rgb) = palette
+        .into_iter()
+        .min_by_key(|(_, candidate)| distance(rgb, *candidate))?;
+
+    (nearest_rgb != rgb).then_some(nearest)
+}
+
+/// The 256 colours of xterm: the ANSI colours, a 6x6x6 colour cube and 24 shades of grey.
+fn xterm_palette() -> impl Iterator<Item = (u8, u8, u8)> {
+    let ansi = ANSI_COLORS.iter().map(|(_, rgb)| *rgb);
+    let cube = CUBE_LEVELS.into_iter().flat_map(|r| {
+        CUBE_LEVELS
+            .into_iter()
+            .flat_map(move |g| CUBE_LEVELS.into_iter().map(move |b| (r, g, b)))
+    });
+    let greys = (0..24).map(|step| {
+        let level = 8 + step * 10;
+        (level, level, level)
+    });
+
+    ansi.chain(cube).chain(greys)
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let channel = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2) as u32;
+    channel(a.0, b.0) + channel(a.1, b.1) + channel(a.2, b.2)
+}
+
+/// The relative luminance of a colour as WCAG defines it, from 0 for black to 1 for white.
+pub fn relative_luminance(color: Hex) -> f64 {
+    let channel = |value: u8| {
+        let value = f64::from(value) / 255.0;
+        match value <= 0.03928 {
+            true => value / 12.92,
+            false => ((value + 0.055) / 1.055).powf(2.4),
+        }
+    };
+
+    let (r, g, b) = color.rgb();
+    0.2126 * channel(r) + 0.7152 * channel(g) + 0.0722 * channel(b)
+}
+
+/// The WCAG contrast ratio of two colours, from 1 for the same colours to 21 for black on white.
+pub fn contrast_ratio(a: Hex, b: Hex) -> f64 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contrast_ratio() {
+        let (black, white) = (Hex::from((0, 0, 0)), Hex::from((255, 255, 255)));
+        assert_eq!(contrast_ratio(black, white), 21.0);
+        assert_eq!(contrast_ratio(white, white), 1.0);
+
+        let grey = Hex::from((0x77, 0x77, 0x77));
+        let ratio = contrast_ratio(grey, white);
+        assert!((ratio - 4.48).abs() < 0.01, "{ratio}");
+    }
+
+    #[test]
+    fn test_nearest_color() {
+        let hex = |text| parse_hex(text).unwrap();
+        assert_eq!(xterm_palette().count(), 256);
+
+        assert_eq!(nearest_color(hex("#5f87af"), ColorDepth::Xterm256), None);
+        assert_eq!(
+            nearest_color(hex("#6088b0"), ColorDepth::Xterm256),
+            Some(PaletteColor::Hex(hex("#5f87af")))
+        );
+        assert_eq!(nearest_color(hex("#cd0000"), ColorDepth::Ansi16), None);
+        assert_eq!(
+            nearest_color(hex("#1010e0"), ColorDepth::Ansi16),
+            Some(PaletteColor::Named("dark_blue"))
+        );
+    }
+
+    #[test]
+    fn test_color_names() {
+        assert!(is_color_name("dark_red"));
+        assert!(!is_color_name("DarkRed"));
+        assert_eq!(normalize_color_name("DarkRed"), Some("dark_red"));
+        assert_eq!(normalize_color_name("light-gray"), None);
+        assert_eq!(normalize_color_name("dark gray"), Some("dark_grey"));
+    }
+}
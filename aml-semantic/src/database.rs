@@ -0,0 +1,1346 @@
+//! A memoized query database for the semantic analysis of a whole workspace, in the style of
+//! salsa.
+//!
+//! The inputs are the text of every template and of its state schema, the state and the element
+//! queries extracted from the Rust sources and the analysis settings, everything else is a derived
+//! query: tokens, ast, state schema, globals, component interfaces, semantic info and the
+//! diagnostics of the whole workspace. Each derived
+//! value is cached along with the revision it was last verified at and the revision it last
+//! changed at.
+//! Setting an input starts a new revision, and a cached value is only computed again when one of
+//! its dependencies changed after it was last verified. When the new value equals the old one, it
+//! keeps its previous `changed_at`, so the queries depending on it are not invalidated either:
+//! editing a template without touching its globals or its interface does not analyze any other
+//! template again.
+//!
+//! The scopes templates are analyzed against are compared by their signature, leaving out where
+//! globals and component uses are: an edit that only moves them does not analyze other templates
+//! either, the locations their diagnostics point to in other templates are moved instead.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use aml_core::{Location, codes};
+use aml_syntax::{Ast, Parser};
+use aml_token::{Lexer, Token, Tokens};
+
+use crate::global_scope::{GlobalScope, GlobalSymbol};
+use crate::lints::{self, UNMATCHED_QUERY, UNUSED_GLOBAL, UNUSED_TEMPLATE};
+use crate::{
+    AnalyzerOptions, ComponentFiles, ComponentInterface, DiagnosticSeverity, DiagnosticTag,
+    ElementQuery, RelatedInformation, Selector, SemanticAnalyzer, SemanticDiagnostic, SemanticInfo,
+    StateSchema, StateSchemaError, Suppressions,
+};
+
+pub type Revision = u64;
+
+/// The state of a template, `None` if it has no state schema nor a Rust struct backing it.
+pub type StateResult = Option<Result<StateSchema, StateSchemaError>>;
+
+/// Diagnostics of several files, by file.
+pub type FileDiagnostics = HashMap<PathBuf, Vec<SemanticDiagnostic>>;
+
+/// Settings shared by the analysis of every template.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    pub options: AnalyzerOptions,
+    /// Where component templates are looked up.
+    pub components: ComponentFiles,
+    /// The template the application renders, which every used template is reachable from.
+    pub root_template: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+struct Input<T> {
+    value: T,
+    changed_at: Revision,
+}
+
+#[derive(Debug)]
+struct Memo<T> {
+    value: Arc<T>,
+    verified_at: Revision,
+    changed_at: Revision,
+}
+
+#[derive(Debug, Default)]
+pub struct Database {
+    revision: Revision,
+
+    // inputs
+    files: HashMap<PathBuf, Input<Arc<str>>>,
+    /// Revision at which a file was last added or removed.
+    files_changed_at: Revision,
+    settings: Input<Settings>,
+    /// The text of the state schema of a template, `None` once the schema is removed.
+    states: HashMap<PathBuf, Input<Option<Arc<str>>>>,
+    /// The state of the Rust structs backing components, by component name.
+    rust_states: Input<Arc<HashMap<String, StateSchema>>>,
+    /// The queries components run on their elements.
+    element_queries: Input<Arc<Vec<ElementQuery>>>,
+
+    // derived queries
+    tokens: HashMap<PathBuf, Memo<Vec<Token>>>,
+    suppressions: HashMap<PathBuf, Memo<Suppressions>>,
+    asts: HashMap<PathBuf, Memo<Ast>>,
+    state_schemas: HashMap<PathBuf, Memo<StateResult>>,
+    globals: HashMap<PathBuf, Memo<Vec<GlobalSymbol>>>,
+    global_scope: Option<Memo<GlobalScope>>,
+    interfaces: HashMap<PathBuf, Memo<ComponentInterface>>,
+    selectors: HashMap<PathBuf, Memo<Vec<Selector>>>,
+    workspace_scope: Option<Memo<GlobalScope>>,
+    semantic_info: HashMap<PathBuf, Memo<SemanticInfo>>,
+    /// The workspace scope each semantic info was analyzed against or last relocated to.
+    analyzed_scopes: HashMap<PathBuf, Arc<GlobalScope>>,
+    state_schema_diagnostics: Option<Memo<FileDiagnostics>>,
+    element_query_diagnostics: Option<Memo<FileDiagnostics>>,
+    unused_diagnostics: Option<Memo<FileDiagnostics>>,
+}
+
+impl<T: Default> Default for Input<T> {
+    fn default() -> Self {
+        Self {
+            value: T::default(),
+            changed_at: 0,
+        }
+    }
+}
+
+impl Database {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn revision(&self) -> Revision {
+        self.revision
+    }
+
+    /// Sets the text of a template, adding it to the workspace if needed.
+    pub fn set_file_text(&mut self, file: &Path, text: impl Into<Arc<str>>) {
+        let text = text.into();
+        if let Some(input) = self.files.get(file)
+            && input.value == text
+        {
+            return;
+        }
+
+        self.revision += 1;
+        let input = Input {
+            value: text,
+            changed_at: self.revision,
+        };
+
+        if self.files.insert(file.into(), input).is_none() {
+            self.files_changed_at = self.revision;
+        }
+    }
+
+    /// Removes a template from the workspace, along with everything derived from it.
+    pub fn remove_file(&mut self, file: &Path) {
+        if self.files.remove(file).is_none() {
+            return;
+        }
+
+        self.revision += 1;
+        self.files_changed_at = self.revision;
+        self.tokens.remove(file);
+        self.suppressions.remove(file);
+        self.asts.remove(file);
+        self.states.remove(file);
+        self.state_schemas.remove(file);
+        self.globals.remove(file);
+        self.interfaces.remove(file);
+        self.selectors.remove(file);
+        self.semantic_info.remove(file);
+        self.analyzed_scopes.remove(file);
+    }
+
+    /// Sets the text of the state schema of a template, see [`StateSchema`]. `None` means the
+    /// template has no schema.
+    pub fn set_state_text(&mut self, file: &Path, text: Option<Arc<str>>) {
+        match self.states.get(file) {
+            Some(input) if input.value == text => return,
+            None if text.is_none() => return,
+            _ => {}
+        }
+
+        self.revision += 1;
+        let input = Input {
+            value: text,
+            changed_at: self.revision,
+        };
+        self.states.insert(file.into(), input);
+    }
+
+    /// Sets the state of the Rust structs backing components, by component name. A state schema
+    /// next to a template takes precedence over its Rust struct.
+    pub fn set_rust_states(&mut self, states: HashMap<String, StateSchema>) {
+        if *self.rust_states.value == states {
+            return;
+        }
+
+        self.revision += 1;
+        self.rust_states = Input {
+            value: Arc::new(states),
+            changed_at: self.revision,
+        };
+    }
+
+    /// Sets the queries the components run on their elements, found in the Rust sources.
+    pub fn set_element_queries(&mut self, queries: Vec<ElementQuery>) {
+        if *self.element_queries.value == queries {
+            return;
+        }
+
+        self.revision += 1;
+        self.element_queries = Input {
+            value: Arc::new(queries),
+            changed_at: self.revision,
+        };
+    }
+
+    pub fn set_settings(&mut self, settings: Settings) {
+        if self.settings.value == settings {
+            return;
+        }
+
+        self.revision += 1;
+        self.settings = Input {
+            value: settings,
+            changed_at: self.revision,
+        };
+    }
+
+    /// Every template of the workspace, sorted so the first declaration of a global is stable.
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files = self.files.keys().cloned().collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+
+    pub fn file_text(&self, file: &Path) -> Option<Arc<str>> {
+        Some(self.files.get(file)?.value.clone())
+    }
+
+    /// The text of the state schema of a template.
+    pub fn state_text(&self, file: &Path) -> Option<Arc<str>> {
+        self.states.get(file)?.value.clone()
+    }
+
+    pub fn tokens(&mut self, file: &Path) -> Option<Arc<Vec<Token>>> {
+        Some(self.tokens_memo(file)?.0)
+    }
+
+    pub fn ast(&mut self, file: &Path) -> Option<Arc<Ast>> {
+        Some(self.ast_memo(file)?.0)
+    }
+
+    /// The state a template is rendered with.
+    pub fn state_schema(&mut self, file: &Path) -> Arc<StateResult> {
+        self.state_schema_memo(file).0
+    }
+
+    /// The globals declared by a template.
+    pub fn globals(&mut self, file: &Path) -> Option<Arc<Vec<GlobalSymbol>>> {
+        Some(self.globals_memo(file)?.0)
+    }
+
+    /// The globals of every template.
+    pub fn global_scope(&mut self) -> Arc<GlobalScope> {
+        self.global_scope_memo().0
+    }
+
+    /// The interface of a template when used as a component.
+    pub fn interface(&mut self, file: &Path) -> Option<Arc<ComponentInterface>> {
+        Some(self.interface_memo(file)?.0)
+    }
+
+    /// The globals of every template along with the interface of every component, which is what
+    /// templates are analyzed against.
+    pub fn workspace_scope(&mut self) -> Arc<GlobalScope> {
+        self.workspace_scope_memo().0
+    }
+
+    /// The analysis of a template. The same `Arc` is returned for as long as the analysis is
+    /// valid, a new one means the diagnostics of the template have to be published again.
+    pub fn semantic_info(&mut self, file: &Path) -> Option<Arc<SemanticInfo>> {
+        Some(self.semantic_info_memo(file)?.0)
+    }
+
+    /// Reports the state schemas that cannot be read, returning the diagnostics of each schema.
+    pub fn state_schema_diagnostics(&mut self) -> Arc<FileDiagnostics> {
+        let mut dependencies = self.files_changed_at;
+        for file in self.files() {
+            let (_, changed_at) = self.state_schema_memo(&file);
+            dependencies = dependencies.max(changed_at);
+        }
+
+        let memo = self.state_schema_diagnostics.take();
+        let memo = revalidate(self.revision, memo, dependencies, PartialEq::eq, || {
+            self.check_state_schemas()
+        });
+        self.state_schema_diagnostics.insert(memo).result().0
+    }
+
+    /// Checks the element queries of the Rust sources against the templates of their component,
+    /// returning the diagnostics of each Rust file.
+    pub fn element_query_diagnostics(&mut self) -> Arc<FileDiagnostics> {
+        let dependencies = self
+            .analyses_changed_at()
+            .max(self.element_queries.changed_at);
+
+        let memo = self.element_query_diagnostics.take();
+        let memo = revalidate(self.revision, memo, dependencies, PartialEq::eq, || {
+            self.check_element_queries()
+        });
+        self.element_query_diagnostics.insert(memo).result().0
+    }
+
+    /// Reports what nothing in the workspace uses: the templates that are neither reachable from
+    /// the root template nor registered as a component, and the globals no template reads. A
+    /// template declaring a global that is read is used. Returns the diagnostics of each template.
+    pub fn unused_diagnostics(&mut self) -> Arc<FileDiagnostics> {
+        let dependencies = self.analyses_changed_at();
+
+        let memo = self.unused_diagnostics.take();
+        let memo = revalidate(self.revision, memo, dependencies, PartialEq::eq, || {
+            self.find_unused()
+        });
+        self.unused_diagnostics.insert(memo).result().0
+    }
+
+    /// The last revision the analysis of any template changed at, which covers its globals, its
+    /// interface and its references, or the workspace and its settings changed at.
+    fn analyses_changed_at(&mut self) -> Revision {
+        let mut changed_at = self.files_changed_at.max(self.settings.changed_at);
+        for file in self.files() {
+            if let Some((_, info_changed_at)) = self.semantic_info_memo(&file) {
+                changed_at = changed_at.max(info_changed_at);
+            }
+        }
+
+        changed_at
+    }
+
+    fn semantic_info_memo(&mut self, file: &Path) -> Option<(Arc<SemanticInfo>, Revision)> {
+        let (ast, ast_changed_at) = self.ast_memo(file)?;
+        let (scope, scope_changed_at) = self.workspace_scope_memo();
+        let (state, state_changed_at) = self.state_schema_memo(file);
+        let (selectors, selectors_changed_at) = self.selectors_memo(file);
+        let (suppressions, suppressions_changed_at) = self.suppressions_memo(file)?;
+        let text = self.file_text(file)?;
+        let settings = self.settings.value.clone();
+        let templates = self.files();
+        let dependencies = ast_changed_at
+            .max(self.files_changed_at)
+            .max(scope_changed_at)
+            .max(state_changed_at)
+            .max(selectors_changed_at)
+            .max(suppressions_changed_at)
+            .max(self.settings.changed_at);
+
+        let mut analyzed = false;
+        let memo = self.semantic_info.remove(file);
+        let mut memo = revalidate(self.revision, memo, dependencies, never_equal, || {
+            analyzed = true;
+            let mut analyzer = SemanticAnalyzer::new(&text, &scope)
+                .with_file_path(file.into())
+                .with_options(settings.options)
+                .with_components(settings.components)
+                .with_templates(templates)
+                .with_suppressions(Suppressions::clone(&suppressions))
+                .with_selectors(Vec::clone(&selectors));
+
+            if let Some(Ok(schema)) = &*state {
+                analyzer = analyzer.with_state(schema);
+            }
+
+            analyzer.analyze(&ast)
+        });
+
+        // the scope may have changed without changing its signature, in which case the related
+        // locations in other templates are moved to where they now are
+        let previous = self.analyzed_scopes.insert(file.into(), scope.clone());
+        if let Some(previous) = previous
+            && !analyzed
+            && !Arc::ptr_eq(&previous, &scope)
+            && let Some(info) = relocate(&memo.value, &previous.moved_locations(&scope))
+        {
+            memo.value = Arc::new(info);
+            memo.changed_at = self.revision;
+        }
+
+        let result = memo.result();
+        self.semantic_info.insert(file.into(), memo);
+        Some(result)
+    }
+
+    fn check_state_schemas(&mut self) -> FileDiagnostics {
+        let mut diagnostics = HashMap::new();
+        for file in self.files() {
+            let (state, _) = self.state_schema_memo(&file);
+            let Some(Err(error)) = &*state else { continue };
+
+            let diagnostic = SemanticDiagnostic::new(
+                &codes::INVALID_STATE_SCHEMA,
+                error.location(),
+                format!("invalid state schema: {error}"),
+                DiagnosticSeverity::Warning,
+            )
+            .with_related_in(
+                file.clone(),
+                Location::default(),
+                "the template of this schema",
+            );
+            diagnostics.insert(StateSchema::path_for(&file), vec![diagnostic]);
+        }
+
+        diagnostics
+    }
+
+    fn check_element_queries(&mut self) -> FileDiagnostics {
+        let queries = self.element_queries.value.clone();
+        let mut diagnostics = FileDiagnostics::new();
+        let Some(severity) = self.settings.value.options.lints.severity(&UNMATCHED_QUERY) else {
+            return diagnostics;
+        };
+
+        for query in queries.iter() {
+            // queries outside of a component are checked against every template
+            let templates = match &query.component {
+                Some(component) => self.component_templates(component).0,
+                None => self.files(),
+            };
+            let infos = templates
+                .iter()
+                .filter_map(|template| self.semantic_info(template))
+                .collect::<Vec<_>>();
+            if infos.is_empty() {
+                continue;
+            }
+
+            let elements = || infos.iter().flat_map(|info| &info.elements);
+            if elements().any(|element| query.selector.matches(element)) {
+                continue;
+            }
+
+            // an attribute that exists with other values is reported on the value
+            let location = match &query.selector {
+                Selector::Attribute {
+                    name,
+                    value: Some(_),
+                } => {
+                    let any_value = Selector::Attribute {
+                        name: name.clone(),
+                        value: None,
+                    };
+                    match elements().any(|element| any_value.matches(element)) {
+                        true => query.value_location.unwrap_or(query.location),
+                        false => query.location,
+                    }
+                }
+                _ => query.location,
+            };
+
+            let mut diagnostic = match &query.component {
+                Some(component) => SemanticDiagnostic::lint(
+                    &UNMATCHED_QUERY,
+                    location,
+                    format!("no {} in component '{component}'", query.selector),
+                )
+                .with_related_in(
+                    templates[0].clone(),
+                    Location::default(),
+                    format!("the template of component '{component}'"),
+                ),
+                None => SemanticDiagnostic::lint(
+                    &UNMATCHED_QUERY,
+                    location,
+                    format!("no {} in any template", query.selector),
+                ),
+            };
+            diagnostic.severity = severity.clone();
+            diagnostics
+                .entry(query.file.clone())
+                .or_default()
+                .push(diagnostic);
+        }
+
+        diagnostics
+    }
+
+    fn find_unused(&mut self) -> FileDiagnostics {
+        let mut diagnostics = FileDiagnostics::new();
+        let files = self.files();
+
+        let infos = files
+            .iter()
+            .filter_map(|file| self.semantic_info(file))
+            .collect::<Vec<_>>();
+        let global_scope = self.global_scope();
+        let mut globals = global_scope
+            .globals
+            .values()
+            .filter_map(|declarations| declarations.first())
+            .collect::<Vec<_>>();
+        globals.sort_by(|a, b| (&a.definition, a.location).cmp(&(&b.definition, b.location)));
+
+        let mut used = vec![];
+        for global in globals {
+            let read = infos.iter().any(|info| {
+                info.symbol_table
+                    .references_to_global(&global.definition, &global.name)
+                    .next()
+                    .is_some()
+            });
+            if read {
+                used.push(global.definition.clone());
+                continue;
+            }
+            if global.name.starts_with('_') {
+                continue;
+            }
+
+            let diagnostic = SemanticDiagnostic::lint(
+                &UNUSED_GLOBAL,
+                global.location,
+                format!("global '{}' is never read", global.name),
+            )
+            .with_tag(DiagnosticTag::Unnecessary);
+            if let Some(diagnostic) = self.workspace_lint(&global.definition, diagnostic) {
+                diagnostics
+                    .entry(global.definition.clone())
+                    .or_default()
+                    .push(diagnostic);
+            }
+        }
+
+        // without a root template, any template may be the one the application renders
+        let Some(root) = self.settings.value.root_template.clone() else { return diagnostics };
+        if !self.files.contains_key(&root) {
+            return diagnostics;
+        }
+
+        let mut roots = vec![root.clone()];
+        roots.extend(self.settings.value.components.registered.values().cloned());
+        roots.extend(used);
+        let (used, _) = self.used_templates(roots);
+
+        for file in files.iter().filter(|file| !used.contains(file)) {
+            let diagnostic = SemanticDiagnostic::lint(
+                &UNUSED_TEMPLATE,
+                Location::default(),
+                "unused template, it is not reachable from the root template and no component is \
+                 registered with it"
+                    .into(),
+            )
+            .with_related_in(root.clone(), Location::default(), "the root template");
+            if let Some(diagnostic) = self.workspace_lint(file, diagnostic) {
+                diagnostics
+                    .entry(file.clone())
+                    .or_default()
+                    .push(diagnostic);
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Applies the level of the lint of a diagnostic and the suppression comments of the template
+    /// it is reported in, `None` if the diagnostic is not reported.
+    fn workspace_lint(
+        &mut self,
+        file: &Path,
+        mut diagnostic: SemanticDiagnostic,
+    ) -> Option<SemanticDiagnostic> {
+        let lint = diagnostic.lint.and_then(lints::find)?;
+        let severity = self.settings.value.options.lints.severity(lint)?;
+        let (suppressions, _) = self.suppressions_memo(file)?;
+        if suppressions.allows(lint, diagnostic.location) {
+            return None;
+        }
+
+        diagnostic.severity = severity;
+        Some(diagnostic)
+    }
+
+    fn tokens_memo(&mut self, file: &Path) -> Option<(Arc<Vec<Token>>, Revision)> {
+        let input = self.files.get(file)?;
+        let (text, dependencies) = (input.value.clone(), input.changed_at);
+
+        let memo = self.tokens.remove(file);
+        let memo = revalidate(self.revision, memo, dependencies, PartialEq::eq, || {
+            Lexer::new(&text).collect()
+        });
+
+        let result = memo.result();
+        self.tokens.insert(file.into(), memo);
+        Some(result)
+    }
+
+    /// The lints allowed by the comments of a template, which are not part of its tokens.
+    fn suppressions_memo(&mut self, file: &Path) -> Option<(Arc<Suppressions>, Revision)> {
+        let input = self.files.get(file)?;
+        let (text, dependencies) = (input.value.clone(), input.changed_at);
+
+        let memo = self.suppressions.remove(file);
+        let memo = revalidate(self.revision, memo, dependencies, PartialEq::eq, || {
+            let mut lexer = Lexer::new(&text);
+            lexer.by_ref().for_each(drop);
+            Suppressions::parse(&text, lexer.comments())
+        });
+
+        let result = memo.result();
+        self.suppressions.insert(file.into(), memo);
+        Some(result)
+    }
+
+    fn ast_memo(&mut self, file: &Path) -> Option<(Arc<Ast>, Revision)> {
+        let (tokens, dependencies) = self.tokens_memo(file)?;
+        let len = self.files.get(file)?.value.len();
+
+        let memo = self.asts.remove(file);
+        let memo = revalidate(self.revision, memo, dependencies, never_equal, || {
+            Parser::new(Tokens::new(tokens.to_vec(), len)).parse()
+        });
+
+        let result = memo.result();
+        self.asts.insert(file.into(), memo);
+        Some(result)
+    }
+
+    fn state_schema_memo(&mut self, file: &Path) -> (Arc<StateResult>, Revision) {
+        let (text, text_changed_at) = match self.states.get(file) {
+            Some(input) => (input.value.clone(), input.changed_at),
+            None => (None, 0),
+        };
+        let rust_states = self.rust_states.value.clone();
+        let name = self.settings.value.components.name(file);
+        let dependencies = text_changed_at
+            .max(self.rust_states.changed_at)
+            .max(self.settings.changed_at);
+
+        let memo = self.state_schemas.remove(file);
+        let memo = revalidate(
+            self.revision,
+            memo,
+            dependencies,
+            PartialEq::eq,
+            || match text {
+                Some(text) => Some(
+                    StateSchema::parse(&text)
+                        .map(|schema| schema.with_definition(StateSchema::path_for(file))),
+                ),
+                None => name
+                    .and_then(|name| rust_states.get(&name).cloned())
+                    .map(Ok),
+            },
+        );
+
+        let result = memo.result();
+        self.state_schemas.insert(file.into(), memo);
+        result
+    }
+
+    fn globals_memo(&mut self, file: &Path) -> Option<(Arc<Vec<GlobalSymbol>>, Revision)> {
+        let (ast, dependencies) = self.ast_memo(file)?;
+        let text = self.file_text(file)?;
+
+        // globals are collected on their own, a global initialized with another one does not
+        // know its type
+        let memo = self.globals.remove(file);
+        let memo = revalidate(self.revision, memo, dependencies, PartialEq::eq, || {
+            SemanticAnalyzer::new(&text, &GlobalScope::new())
+                .with_file_path(file.into())
+                .collect_globals(&ast)
+        });
+
+        let result = memo.result();
+        self.globals.insert(file.into(), memo);
+        Some(result)
+    }
+
+    fn global_scope_memo(&mut self) -> (Arc<GlobalScope>, Revision) {
+        let mut dependencies = self.files_changed_at;
+        let mut globals = vec![];
+        for file in self.files() {
+            let Some((symbols, changed_at)) = self.globals_memo(&file) else { continue };
+            dependencies = dependencies.max(changed_at);
+            globals.push(symbols);
+        }
+
+        let memo = self.global_scope.take();
+        let memo = revalidate(
+            self.revision,
+            memo,
+            dependencies,
+            GlobalScope::same_signature,
+            || {
+                let mut global_scope = GlobalScope::new();
+                for symbol in globals.iter().flat_map(|symbols| symbols.iter()) {
+                    global_scope.declare_global(symbol.clone());
+                }
+                global_scope
+            },
+        );
+
+        self.global_scope.insert(memo).result()
+    }
+
+    fn interface_memo(&mut self, file: &Path) -> Option<(Arc<ComponentInterface>, Revision)> {
+        let (ast, ast_changed_at) = self.ast_memo(file)?;
+        let (scope, scope_changed_at) = self.global_scope_memo();
+        let (state, state_changed_at) = self.state_schema_memo(file);
+        let text = self.file_text(file)?;
+
+        // the interface only depends on the globals and the state, not on the interface of other
+        // components
+        let memo = self.interfaces.remove(file);
+        let dependencies = ast_changed_at.max(scope_changed_at).max(state_changed_at);
+        let memo = revalidate(self.revision, memo, dependencies, PartialEq::eq, || {
+            let mut analyzer = SemanticAnalyzer::new(&text, &scope).with_file_path(file.into());
+            if let Some(Ok(schema)) = &*state {
+                analyzer = analyzer.with_state(schema);
+            }
+
+            analyzer.collect_interface(&ast)
+        });
+
+        let result = memo.result();
+        self.interfaces.insert(file.into(), memo);
+        Some(result)
+    }
+
+    /// The selectors of the queries that can find elements of a template, none unless one of the
+    /// components using it queries its elements.
+    fn selectors_memo(&mut self, file: &Path) -> (Arc<Vec<Selector>>, Revision) {
+        let queries = self.element_queries.value.clone();
+        let mut dependencies = self
+            .element_queries
+            .changed_at
+            .max(self.settings.changed_at)
+            .max(self.files_changed_at);
+
+        let mut templates = HashMap::new();
+        let mut queried = false;
+        let mut selectors = vec![];
+        for query in queries.iter() {
+            if let Some(component) = &query.component {
+                if !templates.contains_key(component) {
+                    let (files, changed_at) = self.component_templates(component);
+                    dependencies = dependencies.max(changed_at);
+                    templates.insert(component, files);
+                }
+
+                if !templates[component].iter().any(|template| template == file) {
+                    continue;
+                }
+                queried = true;
+            }
+
+            selectors.push(query.selector.clone());
+        }
+
+        if !queried {
+            selectors.clear();
+        }
+
+        let memo = self.selectors.remove(file);
+        let memo = revalidate(self.revision, memo, dependencies, PartialEq::eq, || {
+            selectors
+        });
+
+        let result = memo.result();
+        self.selectors.insert(file.into(), memo);
+        result
+    }
+
+    /// The loaded templates a component is made of: its own and the ones of the components it
+    /// uses, along with the revision their interfaces last changed at.
+    fn component_templates(&mut self, component: &str) -> (Vec<PathBuf>, Revision) {
+        let roots = self.settings.value.components.file(component);
+        self.used_templates(roots.into_iter().collect())
+    }
+
+    /// The loaded templates among `roots` and the ones of the components they use, transitively,
+    /// along with the revision their interfaces last changed at.
+    fn used_templates(&mut self, roots: Vec<PathBuf>) -> (Vec<PathBuf>, Revision) {
+        let mut templates = vec![];
+        let mut changed_at = 0;
+        let mut pending = roots;
+
+        while let Some(file) = pending.pop() {
+            if templates.contains(&file) {
+                continue;
+            }
+
+            let Some((interface, interface_changed_at)) = self.interface_memo(&file) else {
+                continue;
+            };
+            changed_at = changed_at.max(interface_changed_at);
+            let components = &self.settings.value.components;
+            pending.extend(
+                interface
+                    .components
+                    .iter()
+                    .filter_map(|used| components.file(&used.name)),
+            );
+            templates.push(file);
+        }
+
+        (templates, changed_at)
+    }
+
+    fn workspace_scope_memo(&mut self) -> (Arc<GlobalScope>, Revision) {
+        let (global_scope, _) = self.global_scope_memo();
+        let mut dependencies = self.files_changed_at.max(self.settings.changed_at);
+
+        let mut interfaces = vec![];
+        for file in self.files() {
+            // the scope holds the current location of every global and component use, even though
+            // moving them alone does not change its signature
+            if let Some((_, changed_at)) = self.globals_memo(&file) {
+                dependencies = dependencies.max(changed_at);
+            }
+            let Some((interface, changed_at)) = self.interface_memo(&file) else { continue };
+            dependencies = dependencies.max(changed_at);
+            if let Some(name) = self.settings.value.components.name(&file) {
+                interfaces.push((name, interface));
+            }
+        }
+
+        let memo = self.workspace_scope.take();
+        let memo = revalidate(
+            self.revision,
+            memo,
+            dependencies,
+            GlobalScope::same_signature,
+            || {
+                let mut scope = GlobalScope::clone(&global_scope);
+                for (name, interface) in interfaces {
+                    scope.declare_interface(&name, ComponentInterface::clone(&interface));
+                }
+                scope
+            },
+        );
+
+        self.workspace_scope.insert(memo).result()
+    }
+}
+
+impl<T> Memo<T> {
+    fn result(&self) -> (Arc<T>, Revision) {
+        (self.value.clone(), self.changed_at)
+    }
+}
+
+/// Returns the cached value if none of its dependencies changed after it was last verified, and
+/// computes it again otherwise. A new value equal to the cached one keeps its `changed_at`.
+fn revalidate<T>(
+    revision: Revision,
+    memo: Option<Memo<T>>,
+    dependencies_changed_at: Revision,
+    equal: impl Fn(&T, &T) -> bool,
+    compute: impl FnOnce() -> T,
+) -> Memo<T> {
+    match memo {
+        Some(mut memo) if dependencies_changed_at <= memo.verified_at => {
+            memo.verified_at = revision;
+            memo
+        }
+        Some(memo) => {
+            let value = compute();
+            let changed_at = match equal(&value, &memo.value) {
+                true => memo.changed_at,
+                false => revision,
+            };
+
+            Memo {
+                value: Arc::new(value),
+                verified_at: revision,
+                changed_at,
+            }
+        }
+        None => Memo {
+            value: Arc::new(compute()),
+            verified_at: revision,
+            changed_at: revision,
+        },
+    }
+}
+
+fn never_equal<T>(_: &T, _: &T) -> bool {
+    false
+}
+
+/// Moves the related locations of the diagnostics in other templates, returns `None` if none of
+/// them moved.
+fn relocate(
+    info: &SemanticInfo,
+    moved: &HashMap<(PathBuf, Location), Location>,
+) -> Option<SemanticInfo> {
+    let moved_to = |related: &RelatedInformation| {
+        let file = related.file.clone()?;
+        moved.get(&(file, related.location)).copied()
+    };
+
+    let any_moved = info
+        .diagnostics
+        .iter()
+        .flat_map(|diagnostic| &diagnostic.related)
+        .any(|related| moved_to(related).is_some());
+    if !any_moved {
+        return None;
+    }
+
+    let mut info = info.clone();
+    for related in info
+        .diagnostics
+        .iter_mut()
+        .flat_map(|diagnostic| &mut diagnostic.related)
+    {
+        if let Some(location) = moved_to(related) {
+            related.location = location;
+        }
+    }
+    Some(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SymbolType, ValueType};
+
+    #[test]
+    fn test_unrelated_edit_keeps_other_analysis() {
+        let mut db = Database::new();
+        let (theme, index) = (Path::new("theme.aml"), Path::new("index.aml"));
+        db.set_file_text(theme, "global color = #ff0000\ntext \"theme\"");
+        db.set_file_text(index, "text [foreground: color] \"index\"");
+
+        let analysis = db.semantic_info(index).unwrap();
+        assert!(analysis.diagnostics.is_empty());
+
+        // the globals of `theme` don't change, so `index` is not analyzed again
+        db.set_file_text(theme, "global color = #ff0000\ntext \"changed\"");
+        assert!(Arc::ptr_eq(&analysis, &db.semantic_info(index).unwrap()));
+
+        // removing the global invalidates `index`
+        db.set_file_text(theme, "text \"changed\"");
+        let analysis = db.semantic_info(index).unwrap();
+        assert_eq!(
+            analysis.diagnostics[0].message,
+            "reference to undefined identifier 'color'"
+        );
+    }
+
+    #[test]
+    fn test_moving_declarations_keeps_other_analysis() {
+        let mut db = Database::new();
+        let (theme, index) = (Path::new("theme.aml"), Path::new("index.aml"));
+        let button = Path::new("button.aml");
+        db.set_file_text(theme, "global color = #ff0000\n@button [label: color]");
+        db.set_file_text(button, "text label");
+        db.set_file_text(index, "text [foreground: color] \"index\"");
+
+        let analysis = db.semantic_info(index).unwrap();
+        assert!(analysis.diagnostics.is_empty());
+
+        // the global and the component use move, but neither their type nor their name change
+        db.set_file_text(
+            theme,
+            "text \"theme\"\nglobal color = #ff0000\n@button [label: color]",
+        );
+        assert!(Arc::ptr_eq(&analysis, &db.semantic_info(index).unwrap()));
+        assert_eq!(
+            db.workspace_scope()
+                .lookup_symbol("color")
+                .unwrap()
+                .location,
+            Location::new(13, 35)
+        );
+        assert!(db.unused_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn test_moving_declarations_moves_related_locations() {
+        let mut db = Database::new();
+        let (theme, index) = (Path::new("theme.aml"), Path::new("index.aml"));
+        db.set_file_text(theme, "global color = #ff0000");
+        db.set_file_text(
+            index,
+            "global color = #00ff00\ntext [foreground: color] \"index\"",
+        );
+
+        let analysis = db.semantic_info(index).unwrap();
+        assert_eq!(
+            analysis.diagnostics[0].related[0].location,
+            Location::new(0, 22)
+        );
+
+        // `index` is not analyzed again, but the global it conflicts with is now further down
+        db.set_file_text(theme, "text \"theme\"\nglobal color = #ff0000");
+        let moved = db.semantic_info(index).unwrap();
+        assert_eq!(
+            moved.diagnostics[0].related[0].location,
+            Location::new(13, 35)
+        );
+        assert!(Arc::ptr_eq(&moved, &db.semantic_info(index).unwrap()));
+    }
+
+    #[test]
+    fn test_workspace_diagnostics_are_memoized() {
+        let mut db = Database::new();
+        let (theme, index) = (Path::new("theme.aml"), Path::new("index.aml"));
+        db.set_file_text(theme, "global color = #ff0000");
+        db.set_file_text(index, "text \"index\"");
+
+        let unused = db.unused_diagnostics();
+        assert_eq!(unused[theme].len(), 1);
+        let queries = db.element_query_diagnostics();
+        assert!(Arc::ptr_eq(&unused, &db.unused_diagnostics()));
+
+        // a query outside of a component does not change the analysis of any template
+        db.set_element_queries(vec![ElementQuery {
+            file: "src/main.rs".into(),
+            component: None,
+            selector: Selector::Tag("vstack".into()),
+            location: Location::default(),
+            value_location: None,
+        }]);
+        assert!(Arc::ptr_eq(&unused, &db.unused_diagnostics()));
+        let diagnostics = db.element_query_diagnostics();
+        assert!(!Arc::ptr_eq(&queries, &diagnostics));
+        assert_eq!(
+            diagnostics[Path::new("src/main.rs")][0].message,
+            "no 'vstack' element in any template"
+        );
+
+        db.set_file_text(index, "text [foreground: color] \"index\"");
+        assert!(db.unused_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn test_component_interface_invalidates_callers() {
+        let mut db = Database::new();
+        let (button, index) = (Path::new("button.aml"), Path::new("index.aml"));
+        db.set_file_text(button, "text [title: label] \"button\"");
+        db.set_file_text(index, "@button [label: \"ok\"]");
+
+        let analysis = db.semantic_info(index).unwrap();
+        assert!(analysis.diagnostics.is_empty());
+
+        db.set_file_text(button, "text [title: caption] \"button\"");
+        let analysis = db.semantic_info(index).unwrap();
+        assert_eq!(
+            analysis.diagnostics[0].message,
+            "component 'button' has no attribute 'label'"
+        );
+
+        let revision = db.revision();
+        db.set_file_text(button, "text [title: caption] \"button\"");
+        assert_eq!(db.revision(), revision);
+    }
+
+    #[test]
+    fn test_registered_component_names() {
+        let mut db = Database::new();
+        let (card, index) = (Path::new("widgets/card.aml"), Path::new("index.aml"));
+        db.set_file_text(card, "text [title: label] \"card\"");
+        db.set_file_text(index, "@panel [lable: \"ok\"]");
+
+        let mut components = ComponentFiles::default();
+        components.registered.insert("panel".into(), card.into());
+        db.set_settings(Settings {
+            components,
+            ..Default::default()
+        });
+
+        let analysis = db.semantic_info(index).unwrap();
+        assert_eq!(
+            analysis.diagnostics[0].message,
+            "component 'panel' has no attribute 'lable'"
+        );
+    }
+
+    #[test]
+    fn test_missing_template_follows_workspace() {
+        let mut db = Database::new();
+        let index = Path::new("templates/index.aml");
+        db.set_file_text(index, "@button");
+        db.set_settings(Settings {
+            components: ComponentFiles {
+                templates_dir: Some("templates".into()),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let analysis = db.semantic_info(index).unwrap();
+        assert_eq!(
+            analysis.diagnostics[0].message,
+            "no template found for component 'button', expected it at 'templates/button.aml'"
+        );
+
+        let button = Path::new("templates/button.aml");
+        db.set_file_text(button, "text \"button\"");
+        assert!(db.semantic_info(index).unwrap().diagnostics.is_empty());
+
+        db.remove_file(button);
+        assert_eq!(db.semantic_info(index).unwrap().diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn test_element_queries() {
+        let mut db = Database::new();
+        let (list, item) = (Path::new("list.aml"), Path::new("item.aml"));
+        db.set_file_text(
+            list,
+            "overflow [id: \"items\"]\n    @item\nvstack [id: \"footer\"]",
+        );
+        db.set_file_text(item, "text [id: \"label\"] \"item\"");
+
+        let mut components = ComponentFiles::default();
+        components.registered.insert("list".into(), list.into());
+        components.registered.insert("item".into(), item.into());
+        db.set_settings(Settings {
+            components,
+            ..Default::default()
+        });
+
+        let query = |selector, location| ElementQuery {
+            file: "src/list.rs".into(),
+            component: Some("list".into()),
+            selector,
+            location,
+            value_location: Some(Location::default()),
+        };
+        let attribute = |name: &str, value: &str| Selector::Attribute {
+            name: name.into(),
+            value: Some(value.into()),
+        };
+        let (first, second) = (
+            Location {
+                start_byte: 1,
+                end_byte: 2,
+            },
+            Location {
+                start_byte: 3,
+                end_byte: 4,
+            },
+        );
+        db.set_element_queries(vec![
+            query(Selector::Tag("overflow".into()), first),
+            // the elements of the components it uses can be queried too
+            query(attribute("id", "label"), first),
+            query(attribute("id", "lst"), first),
+            query(Selector::Tag("overflw".into()), second),
+        ]);
+
+        let diagnostics = db.element_query_diagnostics();
+        let messages = diagnostics[Path::new("src/list.rs")]
+            .iter()
+            .map(|diagnostic| (diagnostic.location, diagnostic.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    Location::default(),
+                    "no element with 'id' set to 'lst' in component 'list'"
+                ),
+                (second, "no 'overflw' element in component 'list'"),
+            ]
+        );
+
+        let analysis = db.semantic_info(list).unwrap();
+        let hints = analysis
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == DiagnosticSeverity::Hint)
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            hints,
+            vec!["no query in the Rust sources targets this 'vstack'"]
+        );
+
+        // templates no query is about get no hints
+        db.set_element_queries(vec![]);
+        let analysis = db.semantic_info(list).unwrap();
+        assert!(analysis.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_lint_levels_and_suppressions() {
+        let mut db = Database::new();
+        let index = Path::new("index.aml");
+        db.set_file_text(
+            index,
+            "let a = 1 // aml-ls: allow(unused-local)\ntext \"index\"",
+        );
+        assert!(db.semantic_info(index).unwrap().diagnostics.is_empty());
+
+        // editing the comment alone does not change the tokens, it still analyzes the template
+        db.set_file_text(
+            index,
+            "let a = 1 // aml-ls: allow(unused-lokal)\ntext \"index\"",
+        );
+        let analysis = db.semantic_info(index).unwrap();
+        let messages = analysis
+            .diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.severity.clone(), diagnostic.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                (DiagnosticSeverity::Warning, "unused local variable 'a'"),
+                (DiagnosticSeverity::Warning, "unknown lint 'unused-lokal'"),
+            ]
+        );
+
+        db.set_settings(Settings {
+            options: AnalyzerOptions {
+                lints: crate::LintLevels::default()
+                    .with(&crate::lints::UNUSED_LOCAL, crate::LintLevel::Deny),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let analysis = db.semantic_info(index).unwrap();
+        assert_eq!(analysis.diagnostics[0].severity, DiagnosticSeverity::Error);
+        assert_eq!(analysis.diagnostics[0].lint, Some("unused-local"));
+    }
+
+    #[test]
+    fn test_unused_templates_and_globals() {
+        let mut db = Database::new();
+        let (index, button, card, theme, old, legacy) = (
+            Path::new("templates/index.aml"),
+            Path::new("templates/button.aml"),
+            Path::new("src/card.aml"),
+            Path::new("templates/theme.aml"),
+            Path::new("templates/old.aml"),
+            Path::new("templates/legacy.aml"),
+        );
+        db.set_file_text(index, "@button\n@panel");
+        db.set_file_text(button, "text [foreground: accent] \"ok\"");
+        db.set_file_text(card, "text \"card\"");
+        db.set_file_text(
+            theme,
+            "global accent = #ff0000\nglobal muted = #888888\n// aml-ls: allow(unused-global)\nglobal spare = 1",
+        );
+        db.set_file_text(old, "// aml-ls: allow-file(unused-template)\ntext \"old\"");
+        db.set_file_text(legacy, "text \"legacy\"");
+
+        let mut components = ComponentFiles {
+            templates_dir: Some("templates".into()),
+            ..Default::default()
+        };
+        components.registered.insert("panel".into(), card.into());
+        db.set_settings(Settings {
+            components,
+            root_template: Some(index.into()),
+            ..Default::default()
+        });
+
+        let diagnostics = db.unused_diagnostics();
+        let mut messages = diagnostics
+            .iter()
+            .flat_map(|(file, diagnostics)| {
+                diagnostics
+                    .iter()
+                    .map(move |diagnostic| (file.as_path(), diagnostic.message.as_str()))
+            })
+            .collect::<Vec<_>>();
+        messages.sort();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    legacy,
+                    "unused template, it is not reachable from the root template and no \
+                     component is registered with it"
+                ),
+                // reading `accent` uses the theme
+                (theme, "global 'muted' is never read"),
+            ]
+        );
+
+        // without a root template, only the globals are checked
+        db.set_settings(Settings::default());
+        let diagnostics = db.unused_diagnostics();
+        assert!(!diagnostics.contains_key(legacy));
+        assert_eq!(diagnostics[theme].len(), 1);
+    }
+
+    #[test]
+    fn test_state_schema() {
+        let mut db = Database::new();
+        let index = Path::new("index.aml");
+        db.set_file_text(index, "text [title: title] \"index\"");
+        db.set_state_text(index, Some("title = \"string\"".into()));
+
+        let analysis = db.semantic_info(index).unwrap();
+        assert!(analysis.diagnostics.is_empty());
+        let symbol = analysis.symbol_table.lookup_symbol("title").unwrap();
+        assert_eq!(symbol.symbol_type, SymbolType::State(ValueType::String));
+        assert_eq!(symbol.location, Location::new(0, 5));
+        // state fields are not attributes of the template as a component
+        assert!(db.interface(index).unwrap().inputs.is_empty());
+
+        db.set_state_text(index, Some("title = \"strng\"".into()));
+        let diagnostics = db.state_schema_diagnostics();
+        let diagnostic = &diagnostics[Path::new("index.state.toml")][0];
+        assert_eq!(
+            diagnostic.message,
+            "invalid state schema: unknown type 'strng' of field 'title'"
+        );
+        assert_eq!(diagnostic.location, Location::new(8, 15));
+        let analysis = db.semantic_info(index).unwrap();
+        assert_eq!(
+            analysis.diagnostics[0].message,
+            "reference to undefined identifier 'title'"
+        );
+
+        db.set_state_text(index, None);
+        let analysis = db.semantic_info(index).unwrap();
+        assert_eq!(
+            analysis.diagnostics[0].message,
+            "reference to undefined identifier 'title'"
+        );
+    }
+
+    #[test]
+    fn test_rust_state_is_overridden_by_schema() {
+        let mut db = Database::new();
+        let index = Path::new("index.aml");
+        db.set_file_text(index, "text [title: title] \"index\"");
+
+        let field = |value_type, location| crate::StateField {
+            name: "title".into(),
+            value_type,
+            rust_type: None,
+            location,
+        };
+        let state = StateSchema {
+            fields: vec![field(ValueType::Number, Location::default())],
+            definition: None,
+        };
+        db.set_rust_states(HashMap::from([("index".into(), state)]));
+        let schema = db.state_schema(index);
+        assert_eq!(
+            Option::clone(&schema).unwrap().unwrap().fields,
+            vec![field(ValueType::Number, Location::default())]
+        );
+
+        db.set_state_text(index, Some("title = \"string\"".into()));
+        let schema = db.state_schema(index);
+        assert_eq!(
+            Option::clone(&schema).unwrap().unwrap().fields,
+            vec![field(ValueType::String, Location::new(0, 5))]
+        );
+    }
+}
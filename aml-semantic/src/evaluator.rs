@@ -0,0 +1,480 @@
+use std::borrow::Cow;
+use std::fmt::Display;
+
+use aml_core::Location;
+use aml_syntax::ast::*;
+use aml_token::{Hex, Operator, Primitive};
+use serde::Serialize;
+
+/// A value known at analysis time.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    Hex(Hex),
+    List(Vec<Value>),
+    Map(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "bool",
+            Value::String(_) => "string",
+            Value::Hex(_) => "hex",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+        }
+    }
+
+    fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Int(int) => Some(*int as f64),
+            Value::Float(float) => Some(*float),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(int) => write!(f, "{int}"),
+            Value::Float(float) => write!(f, "{float}"),
+            Value::Bool(bool) => write!(f, "{bool}"),
+            Value::String(string) => write!(f, "\"{string}\""),
+            Value::Hex(hex) => write!(f, "{hex}"),
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
+            Value::Map(items) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in items.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{key}: {value}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum EvalError {
+    /// The expression depends on something only known at runtime, such as state or a function
+    /// call. This is not an error in the template.
+    #[error("expression is not constant")]
+    NotConstant,
+    #[error("division by zero")]
+    DivisionByZero(Location),
+    #[error("arithmetic overflow")]
+    Overflow(Location),
+    #[error("index {index} is out of bounds for a list of length {len}")]
+    IndexOutOfBounds {
+        location: Location,
+        index: i64,
+        len: usize,
+    },
+    #[error("{message}")]
+    InvalidOperands { location: Location, message: String },
+}
+
+impl EvalError {
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            EvalError::NotConstant => None,
+            EvalError::DivisionByZero(location) => Some(*location),
+            EvalError::Overflow(location) => Some(*location),
+            EvalError::IndexOutOfBounds { location, .. } => Some(*location),
+            EvalError::InvalidOperands { location, .. } => Some(*location),
+        }
+    }
+}
+
+/// Folds constant expressions into values. Identifiers are resolved through `lookup`, which
+/// returns the value of constant `let` and `global` declarations.
+pub struct Evaluator<'src, F> {
+    content: &'src str,
+    lookup: F,
+}
+
+impl<'src, F> Evaluator<'src, F>
+where
+    F: Fn(&str) -> Option<Value>,
+{
+    pub fn new(content: &'src str, lookup: F) -> Self {
+        Self { content, lookup }
+    }
+
+    pub fn evaluate(&self, expr: &Expr) -> Result<Value, EvalError> {
+        match expr {
+            Expr::Primitive(primitive) => Ok(match primitive.value {
+                Primitive::Bool(bool) => Value::Bool(bool),
+                Primitive::Int(int) => Value::Int(int),
+                Primitive::Float(float) => Value::Float(float),
+                Primitive::Hex(hex) => Value::Hex(hex),
+            }),
+            Expr::String(location) => Ok(Value::String(
+                string_literal(self.content, *location).into(),
+            )),
+            Expr::Ident(location) => {
+                (self.lookup)(&self.content[location.to_range()]).ok_or(EvalError::NotConstant)
+            }
+            Expr::List(list) => list
+                .items
+                .iter()
+                .map(|item| self.evaluate(item))
+                .collect::<Result<_, _>>()
+                .map(Value::List),
+            Expr::Map(map) => map
+                .items
+                .iter()
+                .map(|(key, value)| Ok((self.map_key(key)?, self.evaluate(value)?)))
+                .collect::<Result<_, _>>()
+                .map(Value::Map),
+            Expr::Unary(unary) => self.evaluate_unary(unary),
+            Expr::Binary(binary) => self.evaluate_binary(binary),
+            Expr::ArrayIndex(index) => self.evaluate_index(index),
+            Expr::Call(_) => Err(EvalError::NotConstant),
+            Expr::Error(_) => Err(EvalError::NotConstant),
+        }
+    }
+
+    fn evaluate_unary(&self, unary: &Unary) -> Result<Value, EvalError> {
+        let value = self.evaluate(&unary.expr)?;
+
+        match (unary.op, value) {
+            (Operator::Not, Value::Bool(bool)) => Ok(Value::Bool(!bool)),
+            (Operator::Minus, Value::Int(int)) => int
+                .checked_neg()
+                .map(Value::Int)
+                .ok_or(EvalError::Overflow(unary.location)),
+            (Operator::Minus, Value::Float(float)) => Ok(Value::Float(-float)),
+            (op, value) => Err(EvalError::InvalidOperands {
+                location: unary.location,
+                message: format!("cannot apply '{}' to {}", op_symbol(op), value.type_name()),
+            }),
+        }
+    }
+
+    fn evaluate_binary(&self, binary: &Binary) -> Result<Value, EvalError> {
+        // member access only needs the name of the field, not its value
+        if binary.op == Operator::Dot {
+            let lhs = self.evaluate(&binary.lhs)?;
+            let Expr::Ident(field) = *binary.rhs else { return Err(EvalError::NotConstant) };
+            let field = &self.content[field.to_range()];
+
+            return match lhs {
+                Value::Map(items) => items
+                    .into_iter()
+                    .find(|(key, _)| key == field)
+                    .map(|(_, value)| value)
+                    .ok_or(EvalError::NotConstant),
+                _ => Err(EvalError::NotConstant),
+            };
+        }
+
+        // an index past the end of a list is null, which is what the fallback is for
+        if binary.op == Operator::Either {
+            return match self.evaluate(&binary.lhs) {
+                Err(EvalError::IndexOutOfBounds { .. }) => self.evaluate(&binary.rhs),
+                result => result,
+            };
+        }
+
+        let lhs = self.evaluate(&binary.lhs)?;
+        let rhs = self.evaluate(&binary.rhs)?;
+        let location = binary.location;
+
+        match binary.op {
+            Operator::Plus | Operator::Minus | Operator::Mul | Operator::Div | Operator::Mod => {
+                self.evaluate_arithmetic(binary.op, lhs, rhs, location)
+            }
+            Operator::EqualEqual => Ok(Value::Bool(values_equal(&lhs, &rhs))),
+            Operator::NotEqual => Ok(Value::Bool(!values_equal(&lhs, &rhs))),
+            Operator::GreaterThan
+            | Operator::GreaterThanOrEqual
+            | Operator::LessThan
+            | Operator::LessThanOrEqual => self.evaluate_comparison(binary.op, lhs, rhs, location),
+            Operator::And | Operator::Or => match (lhs, rhs) {
+                (Value::Bool(lhs), Value::Bool(rhs)) => Ok(Value::Bool(match binary.op {
+                    Operator::And => lhs && rhs,
+                    _ => lhs || rhs,
+                })),
+                (lhs, rhs) => Err(invalid_operands(binary.op, &lhs, &rhs, location)),
+            },
+            _ => Err(EvalError::NotConstant),
+        }
+    }
+
+    fn evaluate_arithmetic(
+        &self,
+        op: Operator,
+        lhs: Value,
+        rhs: Value,
+        location: Location,
+    ) -> Result<Value, EvalError> {
+        match (lhs, rhs) {
+            (Value::Int(lhs), Value::Int(rhs)) => {
+                let result = match op {
+                    Operator::Plus => lhs.checked_add(rhs),
+                    Operator::Minus => lhs.checked_sub(rhs),
+                    Operator::Mul => lhs.checked_mul(rhs),
+                    Operator::Div | Operator::Mod if rhs == 0 => {
+                        return Err(EvalError::DivisionByZero(location));
+                    }
+                    Operator::Div => lhs.checked_div(rhs),
+                    _ => lhs.checked_rem(rhs),
+                };
+                result.map(Value::Int).ok_or(EvalError::Overflow(location))
+            }
+            (Value::String(lhs), Value::String(rhs)) if op == Operator::Plus => {
+                Ok(Value::String(lhs + &rhs))
+            }
+            (lhs, rhs) => {
+                let (Some(l), Some(r)) = (lhs.as_float(), rhs.as_float()) else {
+                    return Err(invalid_operands(op, &lhs, &rhs, location));
+                };
+
+                let result = match op {
+                    Operator::Plus => l + r,
+                    Operator::Minus => l - r,
+                    Operator::Mul => l * r,
+                    Operator::Div | Operator::Mod if r == 0.0 => {
+                        return Err(EvalError::DivisionByZero(location));
+                    }
+                    Operator::Div => l / r,
+                    _ => l % r,
+                };
+                Ok(Value::Float(result))
+            }
+        }
+    }
+
+    fn evaluate_comparison(
+        &self,
+        op: Operator,
+        lhs: Value,
+        rhs: Value,
+        location: Location,
+    ) -> Result<Value, EvalError> {
+        let ordering = match (&lhs, &rhs) {
+            (Value::String(l), Value::String(r)) => l.partial_cmp(r),
+            _ => match (lhs.as_float(), rhs.as_float()) {
+                (Some(l), Some(r)) => l.partial_cmp(&r),
+                _ => return Err(invalid_operands(op, &lhs, &rhs, location)),
+            },
+        };
+
+        let Some(ordering) = ordering else { return Ok(Value::Bool(false)) };
+
+        Ok(Value::Bool(match op {
+            Operator::GreaterThan => ordering.is_gt(),
+            Operator::GreaterThanOrEqual => ordering.is_ge(),
+            Operator::LessThan => ordering.is_lt(),
+            _ => ordering.is_le(),
+        }))
+    }
+
+    fn evaluate_index(&self, index: &ArrayIndex) -> Result<Value, EvalError> {
+        let lhs = self.evaluate(&index.lhs)?;
+        let key = self.evaluate(&index.index)?;
+
+        match (lhs, key) {
+            (Value::List(items), Value::Int(i)) => {
+                let len = items.len();
+                usize::try_from(i)
+                    .ok()
+                    .and_then(|i| items.into_iter().nth(i))
+                    .ok_or(EvalError::IndexOutOfBounds {
+                        location: index.location,
+                        index: i,
+                        len,
+                    })
+            }
+            (Value::Map(items), Value::String(key)) => items
+                .into_iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, value)| value)
+                .ok_or(EvalError::NotConstant),
+            (lhs, key) => Err(EvalError::InvalidOperands {
+                location: index.location,
+                message: format!("cannot index {} with {}", lhs.type_name(), key.type_name()),
+            }),
+        }
+    }
+
+    /// Map keys are written as identifiers or strings, neither of which refer to a variable.
+    fn map_key(&self, key: &Expr) -> Result<String, EvalError> {
+        match key {
+            Expr::Ident(location) => Ok(self.content[location.to_range()].into()),
+            Expr::String(location) => Ok(string_literal(self.content, *location).into()),
+            _ => Err(EvalError::NotConstant),
+        }
+    }
+}
+
+/// The value of a string literal, without its delimiters and with its escape sequences replaced.
+pub(crate) fn string_literal(content: &str, location: Location) -> Cow<'_, str> {
+    let literal = &content[location.to_range()];
+    // strip the delimiters, the lexer includes them in the location
+    let literal = literal
+        .get(1..literal.len().saturating_sub(1))
+        .unwrap_or_default();
+    if !literal.contains('\\') {
+        return Cow::Borrowed(literal);
+    }
+
+    let mut value = String::with_capacity(literal.len());
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some('r') => value.push('\r'),
+            Some('0') => value.push('\0'),
+            // \\, \" and \' stand for the character itself, like any other escaped character
+            Some(escaped) => value.push(escaped),
+            None => value.push('\\'),
+        }
+    }
+
+    Cow::Owned(value)
+}
+
+fn values_equal(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs.as_float(), rhs.as_float()) {
+        (Some(l), Some(r)) => l == r,
+        _ => lhs == rhs,
+    }
+}
+
+fn invalid_operands(op: Operator, lhs: &Value, rhs: &Value, location: Location) -> EvalError {
+    EvalError::InvalidOperands {
+        location,
+        message: format!(
+            "cannot apply '{}' to {} and {}",
+            op_symbol(op),
+            lhs.type_name(),
+            rhs.type_name()
+        ),
+    }
+}
+
+fn op_symbol(op: Operator) -> &'static str {
+    match op {
+        Operator::Plus => "+",
+        Operator::Minus => "-",
+        Operator::Mul => "*",
+        Operator::Div => "/",
+        Operator::Mod => "%",
+        Operator::Not => "!",
+        Operator::GreaterThan => ">",
+        Operator::GreaterThanOrEqual => ">=",
+        Operator::LessThan => "<",
+        Operator::LessThanOrEqual => "<=",
+        Operator::EqualEqual => "==",
+        Operator::NotEqual => "!=",
+        Operator::And => "&&",
+        Operator::Or => "||",
+        Operator::Either => "?",
+        _ => "operator",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aml_syntax::expressions::parse_expression;
+    use aml_token::{Lexer, Tokens};
+
+    use super::*;
+
+    fn evaluate(input: &str) -> Result<Value, EvalError> {
+        let tokens = Lexer::new(input).collect();
+        let mut tokens = Tokens::new(tokens, input.len());
+        let expr = parse_expression(&mut tokens);
+
+        let lookup = |name: &str| match name {
+            "width" => Some(Value::Int(10)),
+            "cfg" => Some(Value::Map(vec![(
+                "title".into(),
+                Value::String("x".into()),
+            )])),
+            _ => None,
+        };
+
+        Evaluator::new(input, lookup).evaluate(&expr)
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(evaluate("5 + 2"), Ok(Value::Int(7)));
+        assert_eq!(evaluate("width * 2 - 1"), Ok(Value::Int(19)));
+        assert_eq!(evaluate("7 / 2.0"), Ok(Value::Float(3.5)));
+        assert_eq!(evaluate("-(1 + 2)"), Ok(Value::Int(-3)));
+        assert_eq!(evaluate("'a' + \"b\""), Ok(Value::String("ab".into())));
+        assert_eq!(
+            evaluate(r#""say \"hi\"\n" + 'it\'s'"#),
+            Ok(Value::String("say \"hi\"\nit's".into()))
+        );
+    }
+
+    #[test]
+    fn test_comparisons_and_booleans() {
+        assert_eq!(evaluate("width > 5 && !false"), Ok(Value::Bool(true)));
+        assert_eq!(evaluate("1 == 1.0"), Ok(Value::Bool(true)));
+        assert_eq!(evaluate("'a' != 'a' || 2 <= 1"), Ok(Value::Bool(false)));
+    }
+
+    #[test]
+    fn test_collections() {
+        assert_eq!(evaluate("[1, 2, 3][1]"), Ok(Value::Int(2)));
+        assert_eq!(evaluate("cfg.title"), Ok(Value::String("x".into())));
+        assert_eq!(evaluate("[1][3] ? 0"), Ok(Value::Int(0)));
+        assert_eq!(evaluate("[1][0] ? 0"), Ok(Value::Int(1)));
+        assert_eq!(
+            evaluate("{a: 1}"),
+            Ok(Value::Map(vec![("a".into(), Value::Int(1))]))
+        );
+    }
+
+    #[test]
+    fn test_not_constant() {
+        assert_eq!(evaluate("state + 1"), Err(EvalError::NotConstant));
+        assert_eq!(evaluate("to_upper('a')"), Err(EvalError::NotConstant));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            evaluate("width / (2 - 2)"),
+            Err(EvalError::DivisionByZero(Location::new(0, 14)))
+        );
+        assert_eq!(
+            evaluate("true + 1").unwrap_err().to_string(),
+            "cannot apply '+' to bool and int"
+        );
+        assert_eq!(
+            evaluate("[1][3]").unwrap_err().to_string(),
+            "index 3 is out of bounds for a list of length 1"
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use crate::ValueType;
+
+/// The type of a function parameter or return value.
+///
+/// Unlike [`ValueType`] this only describes the shape of a value, which is all the catalog needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeKind {
+    Any,
+    String,
+    Number,
+    Boolean,
+    List,
+    /// Either a string or a list.
+    Sequence,
+}
+
+impl TypeKind {
+    /// Returns `true` if a value of the given type can be passed where this kind is expected.
+    /// Values of an unknown type are always accepted, and a union is accepted if all of its
+    /// members are.
+    pub fn accepts(self, value_type: &ValueType) -> bool {
+        // null is handled by the functions themselves, so only the other members are checked
+        if let ValueType::Union(members) = value_type {
+            return members
+                .iter()
+                .filter(|member| **member != ValueType::Null)
+                .all(|member| self.accepts(member));
+        }
+
+        matches!(
+            (self, value_type),
+            (_, ValueType::Unknown)
+                | (TypeKind::Any, _)
+                | (TypeKind::String, ValueType::String)
+                | (TypeKind::Number, ValueType::Number)
+                | (TypeKind::Boolean, ValueType::Boolean)
+                | (TypeKind::List, ValueType::List(_))
+                | (TypeKind::Sequence, ValueType::String | ValueType::List(_))
+        )
+    }
+
+    pub fn value_type(self) -> ValueType {
+        match self {
+            TypeKind::Any | TypeKind::Sequence => ValueType::Unknown,
+            TypeKind::String => ValueType::String,
+            TypeKind::Number => ValueType::Number,
+            TypeKind::Boolean => ValueType::Boolean,
+            TypeKind::List => ValueType::List(Box::new(ValueType::Unknown)),
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            TypeKind::Any => "any value",
+            TypeKind::String => "a string",
+            TypeKind::Number => "a number",
+            TypeKind::Boolean => "a boolean",
+            TypeKind::List => "a list",
+            TypeKind::Sequence => "a string or a list",
+        }
+    }
+}
+
+/// Signature of a built-in template function.
+#[derive(Debug)]
+pub struct Function {
+    pub name: &'static str,
+    pub params: &'static [TypeKind],
+    /// Number of trailing parameters that may be left out.
+    pub optional: usize,
+    pub returns: TypeKind,
+    pub description: &'static str,
+}
+
+impl Function {
+    pub fn min_args(&self) -> usize {
+        self.params.len() - self.optional
+    }
+
+    pub fn max_args(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Human readable signature, e.g. `truncate(string, number) -> string`.
+    pub fn signature(&self) -> String {
+        let params = self
+            .params
+            .iter()
+            .enumerate()
+            .map(|(index, param)| {
+                let name = type_name(*param);
+                match index >= self.min_args() {
+                    true => format!("{name}?"),
+                    false => name.into(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!("{}({params}) -> {}", self.name, type_name(self.returns))
+    }
+}
+
+fn type_name(kind: TypeKind) -> &'static str {
+    match kind {
+        TypeKind::Any => "any",
+        TypeKind::String => "string",
+        TypeKind::Number => "number",
+        TypeKind::Boolean => "bool",
+        TypeKind::List => "list",
+        TypeKind::Sequence => "string | list",
+    }
+}
+
+/// The functions available in Anathema templates.
+pub const FUNCTIONS: &[Function] = &[
+    Function {
+        name: "to_upper",
+        params: &[TypeKind::String],
+        optional: 0,
+        returns: TypeKind::String,
+        description: "Converts a string to upper case.",
+    },
+    Function {
+        name: "to_lower",
+        params: &[TypeKind::String],
+        optional: 0,
+        returns: TypeKind::String,
+        description: "Converts a string to lower case.",
+    },
+    Function {
+        name: "to_str",
+        params: &[TypeKind::Any],
+        optional: 0,
+        returns: TypeKind::String,
+        description: "Converts a value to its string representation.",
+    },
+    Function {
+        name: "to_int",
+        params: &[TypeKind::Any],
+        optional: 0,
+        returns: TypeKind::Number,
+        description: "Converts a value to an integer.",
+    },
+    Function {
+        name: "to_float",
+        params: &[TypeKind::Any],
+        optional: 0,
+        returns: TypeKind::Number,
+        description: "Converts a value to a float.",
+    },
+    Function {
+        name: "round",
+        params: &[TypeKind::Number, TypeKind::Number],
+        optional: 1,
+        returns: TypeKind::Number,
+        description: "Rounds a number to the given number of decimals (default 0).",
+    },
+    Function {
+        name: "contains",
+        params: &[TypeKind::Sequence, TypeKind::Any],
+        optional: 0,
+        returns: TypeKind::Boolean,
+        description: "Returns true if a string contains a substring, or a list contains a value.",
+    },
+    Function {
+        name: "join",
+        params: &[TypeKind::List, TypeKind::String],
+        optional: 0,
+        returns: TypeKind::String,
+        description: "Joins the elements of a list with a separator.",
+    },
+    Function {
+        name: "truncate",
+        params: &[TypeKind::String, TypeKind::Number],
+        optional: 0,
+        returns: TypeKind::String,
+        description: "Truncates a string to the given width.",
+    },
+    Function {
+        name: "width",
+        params: &[TypeKind::String],
+        optional: 0,
+        returns: TypeKind::Number,
+        description: "Returns the display width of a string.",
+    },
+];
+
+/// Looks up a built-in function by name.
+pub fn lookup_function(name: &str) -> Option<&'static Function> {
+    FUNCTIONS.iter().find(|function| function.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature() {
+        assert_eq!(
+            lookup_function("round").unwrap().signature(),
+            "round(number, number?) -> number"
+        );
+        assert_eq!(
+            lookup_function("contains").unwrap().signature(),
+            "contains(string | list, any) -> bool"
+        );
+        assert!(lookup_function("to_title").is_none());
+    }
+}
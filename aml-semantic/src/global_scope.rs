@@ -1,41 +1,182 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
 use aml_core::Location;
 
-use crate::SymbolType;
+use crate::{ComponentInterface, ComponentUse, SymbolType, Value};
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct GlobalSymbol {
     pub name: String,
     pub location: Location,
     pub symbol_type: SymbolType,
     pub definition: PathBuf,
+    /// The value of the global, if it can be evaluated during analysis.
+    pub value: Option<Value>,
 }
 
-#[derive(Debug, Default)]
+impl GlobalSymbol {
+    /// Whether two declarations only differ by their location.
+    pub fn same_signature(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.symbol_type == other.symbol_type
+            && self.definition == other.definition
+            && self.value == other.value
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct GlobalScope {
-    pub globals: HashMap<String, GlobalSymbol>,
+    /// Every declaration of a global, in the order they were discovered. Only the first one is
+    /// used when resolving the name, any other declaration is a conflict.
+    pub globals: HashMap<String, Vec<GlobalSymbol>>,
+    /// Inferred interfaces of the component templates, by component name.
+    pub interfaces: HashMap<String, ComponentInterface>,
 }
 
 impl GlobalScope {
     pub fn new() -> Self {
         Self {
             globals: Default::default(),
+            interfaces: Default::default(),
         }
     }
 
     pub fn declare_global(&mut self, symbol: GlobalSymbol) {
-        if self.globals.contains_key(&symbol.name) {
+        let declarations = self.globals.entry(symbol.name.clone()).or_default();
+
+        let already_declared = declarations.iter().any(|declaration| {
+            declaration.definition == symbol.definition && declaration.location == symbol.location
+        });
+
+        if already_declared {
             return;
-        };
+        }
+
+        declarations.push(symbol);
+    }
+
+    /// Records the interface of the component `name`.
+    pub fn declare_interface(&mut self, name: &str, interface: ComponentInterface) {
+        self.interfaces.insert(name.into(), interface);
+    }
+
+    pub fn interface(&self, name: &str) -> Option<&ComponentInterface> {
+        self.interfaces.get(name)
+    }
+
+    /// Finds a chain of component uses leading from the component `from` to the component `to`.
+    /// Each step is the interface of a component along with its use of the next one, the chain is
+    /// empty if `from` is `to`.
+    pub fn component_path(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> Option<Vec<(&ComponentInterface, &ComponentUse)>> {
+        let mut visited = HashSet::new();
+        let mut path = vec![];
+        self.find_component_path(from, to, &mut visited, &mut path)
+            .then_some(path)
+    }
+
+    fn find_component_path<'a>(
+        &'a self,
+        from: &str,
+        to: &str,
+        visited: &mut HashSet<String>,
+        path: &mut Vec<(&'a ComponentInterface, &'a ComponentUse)>,
+    ) -> bool {
+        if from == to {
+            return true;
+        }
+
+        if !visited.insert(from.to_string()) {
+            return false;
+        }
+
+        let Some(interface) = self.interfaces.get(from) else { return false };
+        for component in interface.components.iter() {
+            path.push((interface, component));
+            if self.find_component_path(&component.name, to, visited, path) {
+                return true;
+            }
+            path.pop();
+        }
 
-        let name = symbol.name.clone();
-        self.globals.insert(name, symbol);
+        false
     }
 
     pub fn lookup_symbol(&self, name: &str) -> Option<&GlobalSymbol> {
-        self.globals.get(name)
+        self.globals.get(name)?.first()
+    }
+
+    pub fn declarations(&self, name: &str) -> &[GlobalSymbol] {
+        self.globals
+            .get(name)
+            .map_or(&[], |declarations| declarations)
+    }
+
+    /// Whether two scopes only differ by where their globals and components are declared, which
+    /// does not change the analysis of the templates using them.
+    pub fn same_signature(&self, other: &Self) -> bool {
+        let same_globals = |(name, declarations): (&String, &Vec<GlobalSymbol>)| {
+            other.globals.get(name).is_some_and(|others| {
+                declarations.len() == others.len()
+                    && declarations
+                        .iter()
+                        .zip(others)
+                        .all(|(declaration, other)| declaration.same_signature(other))
+            })
+        };
+        let same_interfaces = |(name, interface): (&String, &ComponentInterface)| {
+            other
+                .interfaces
+                .get(name)
+                .is_some_and(|other| interface.same_signature(other))
+        };
+
+        self.globals.len() == other.globals.len()
+            && self.globals.iter().all(same_globals)
+            && self.interfaces.len() == other.interfaces.len()
+            && self.interfaces.iter().all(same_interfaces)
+    }
+
+    /// Where the globals, component inputs and component uses of this scope moved to in `current`,
+    /// a scope with the same signature, by the template and location they had in this one.
+    pub fn moved_locations(&self, current: &Self) -> HashMap<(PathBuf, Location), Location> {
+        let mut moved = HashMap::new();
+        let mut record = |definition: &PathBuf, from: Location, to: Location| {
+            if from != to {
+                moved.insert((definition.clone(), from), to);
+            }
+        };
+
+        for (name, declarations) in &self.globals {
+            let Some(others) = current.globals.get(name) else { continue };
+            for (declaration, other) in declarations.iter().zip(others) {
+                record(
+                    &declaration.definition,
+                    declaration.location,
+                    other.location,
+                );
+            }
+        }
+
+        for (name, interface) in &self.interfaces {
+            let Some(other) = current.interfaces.get(name) else { continue };
+            for (input, other_input) in interface.inputs.iter().zip(&other.inputs) {
+                record(&interface.definition, input.location, other_input.location);
+            }
+            for (component, other_component) in interface.components.iter().zip(&other.components) {
+                record(
+                    &interface.definition,
+                    component.location,
+                    other_component.location,
+                );
+            }
+        }
+
+        moved
     }
 
     // pub async fn get_global_location(&self, name: &str) -> Option<PathBuf> {
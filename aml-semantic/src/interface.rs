@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use aml_core::Location;
+
+use crate::ValueType;
+
+/// The external interface of a component, inferred from its template: what the template reads
+/// without declaring it, and the slots it renders.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComponentInterface {
+    /// The template the interface was inferred from.
+    pub definition: PathBuf,
+    /// Identifiers read but not declared by the template, in order of their first use.
+    pub inputs: Vec<ComponentInput>,
+    /// Names of the `$slot`s used by the template.
+    pub slots: Vec<String>,
+    /// Components used by the template, which make up the component graph.
+    pub components: Vec<ComponentUse>,
+}
+
+/// Where the template of each component lives. Components registered in Rust have an explicit name
+/// and template, the others are named after their template in the templates directory, `@button`
+/// being `button.aml`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComponentFiles {
+    pub templates_dir: Option<PathBuf>,
+    pub registered: HashMap<String, PathBuf>,
+}
+
+impl ComponentFiles {
+    /// The template of a component, or `None` if there is no templates directory to look it up in.
+    pub fn file(&self, name: &str) -> Option<PathBuf> {
+        match self.registered.get(name) {
+            Some(file) => Some(file.clone()),
+            None => Some(
+                self.templates_dir
+                    .as_ref()?
+                    .join(name)
+                    .with_extension("aml"),
+            ),
+        }
+    }
+
+    /// The name of the component a template is used as.
+    pub fn name(&self, file: &Path) -> Option<String> {
+        let registered = self
+            .registered
+            .iter()
+            .filter(|(_, registered)| *registered == file)
+            .map(|(name, _)| name)
+            .min();
+
+        match registered {
+            Some(name) => Some(name.clone()),
+            None => Some(file.file_stem()?.to_str()?.into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentUse {
+    pub name: String,
+    /// Location of the component name, e.g. `button` in `@button`.
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentInput {
+    pub name: String,
+    /// The type the template expects, inferred from how the input is used.
+    pub value_type: ValueType,
+    /// Where the input is first read.
+    pub location: Location,
+}
+
+impl ComponentInterface {
+    pub fn input(&self, name: &str) -> Option<&ComponentInput> {
+        self.inputs.iter().find(|input| input.name == name)
+    }
+
+    /// Whether two interfaces only differ by where their inputs and components are in the
+    /// template.
+    pub fn same_signature(&self, other: &Self) -> bool {
+        let same_input = |(input, other): (&ComponentInput, &ComponentInput)| {
+            input.name == other.name && input.value_type == other.value_type
+        };
+        let same_use = |(used, other): (&ComponentUse, &ComponentUse)| used.name == other.name;
+
+        self.definition == other.definition
+            && self.slots == other.slots
+            && self.inputs.len() == other.inputs.len()
+            && self.inputs.iter().zip(&other.inputs).all(same_input)
+            && self.components.len() == other.components.len()
+            && self.components.iter().zip(&other.components).all(same_use)
+    }
+
+    pub(crate) fn add_input(&mut self, name: &str, location: Location) {
+        if self.input(name).is_none() {
+            self.inputs.push(ComponentInput {
+                name: name.into(),
+                value_type: ValueType::Unknown,
+                location,
+            });
+        }
+    }
+
+    /// Narrows the type of an input whose type is not known yet.
+    pub(crate) fn infer_type(&mut self, name: &str, value_type: ValueType) {
+        let input = self.inputs.iter_mut().find(|input| input.name == name);
+        if let Some(input) = input
+            && input.value_type == ValueType::Unknown
+        {
+            input.value_type = value_type;
+        }
+    }
+
+    pub(crate) fn add_component(&mut self, name: &str, location: Location) {
+        self.components.push(ComponentUse {
+            name: name.into(),
+            location,
+        });
+    }
+
+    pub(crate) fn add_slot(&mut self, name: &str) {
+        if !self.slots.iter().any(|slot| slot == name) {
+            self.slots.push(name.into());
+        }
+    }
+}
@@ -1,8 +1,26 @@
 pub mod analyzer;
+pub mod color;
+pub mod database;
+pub mod evaluator;
+pub mod functions;
 pub mod global_scope;
+pub mod interface;
+pub mod lints;
+pub mod queries;
 pub mod scope;
+pub mod state;
 pub mod symbol_table;
 
-pub use analyzer::{DiagnosticSeverity, SemanticAnalyzer, SemanticDiagnostic, SemanticInfo};
+pub use analyzer::{
+    AnalyzerOptions, ComputedValue, DiagnosticSeverity, DiagnosticTag, Fix, RelatedInformation,
+    SemanticAnalyzer, SemanticDiagnostic, SemanticInfo,
+};
+pub use database::{Database, FileDiagnostics, Settings};
+pub use evaluator::{EvalError, Evaluator, Value};
+pub use functions::{Function, TypeKind};
+pub use interface::{ComponentFiles, ComponentInput, ComponentInterface, ComponentUse};
+pub use lints::{Lint, LintLevel, LintLevels, Suppressions};
+pub use queries::{ElementAttribute, ElementQuery, Selector, TemplateElement};
 pub use scope::{ScopeAnalyzer, ScopeInfo};
-pub use symbol_table::{Symbol, SymbolTable, SymbolType, ValueType};
+pub use state::{StateField, StateSchema, StateSchemaError};
+pub use symbol_table::{Reference, ReferenceTarget, Symbol, SymbolTable, SymbolType, ValueType};
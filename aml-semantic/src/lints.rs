@@ -0,0 +1,360 @@
+//! The checks that report questionable templates rather than broken ones. Each lint has a name and
+//! a default level, which projects change in the `[lints]` table of `aml_ls.toml`:
+//!
+//! ```toml
+//! [lints]
+//! shadowing = "warn"
+//! unused-local = "allow"
+//! duplicate-attribute = "deny"
+//! ```
+//!
+//! and templates for a single line with a comment, either at the end of the line or on the line
+//! before it, or for the whole template:
+//!
+//! ```text
+//! // aml-ls: allow(unused-local)
+//! let label = "unused"
+//! text [id: "title"] "title" // aml-ls: allow(unqueried-element)
+//! // aml-ls: allow-file(shadowing)
+//! ```
+
+use std::collections::HashMap;
+
+pub use aml_config::LintLevel;
+use aml_core::{Code, Location, codes};
+
+use crate::DiagnosticSeverity;
+
+const SUPPRESSION_PREFIX: &str = "aml-ls:";
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Lint {
+    /// The code of the lint, whose name is the name of the lint.
+    pub code: &'static Code,
+    pub default_level: LintLevel,
+    /// The severity of the diagnostics of the lint when it warns, denied lints report errors.
+    pub severity: DiagnosticSeverity,
+    pub description: &'static str,
+}
+
+pub static CONFLICTING_GLOBALS: Lint = Lint {
+    code: &codes::CONFLICTING_GLOBALS,
+    default_level: LintLevel::Warn,
+    severity: DiagnosticSeverity::Warning,
+    description: "a global is declared in several templates",
+};
+
+pub static SHADOWING: Lint = Lint {
+    code: &codes::SHADOWING,
+    default_level: LintLevel::Allow,
+    severity: DiagnosticSeverity::Warning,
+    description: "a local or loop binding hides another variable or a global",
+};
+
+pub static UNUSED_LOCAL: Lint = Lint {
+    code: &codes::UNUSED_LOCAL,
+    default_level: LintLevel::Warn,
+    severity: DiagnosticSeverity::Warning,
+    description: "a local variable or loop binding is never read",
+};
+
+pub static UNKNOWN_ATTRIBUTE: Lint = Lint {
+    code: &codes::UNKNOWN_ATTRIBUTE,
+    default_level: LintLevel::Warn,
+    severity: DiagnosticSeverity::Warning,
+    description: "a component is given an attribute its template does not read",
+};
+
+pub static MISSING_ATTRIBUTES: Lint = Lint {
+    code: &codes::MISSING_ATTRIBUTES,
+    default_level: LintLevel::Allow,
+    severity: DiagnosticSeverity::Warning,
+    description: "a component is used without an attribute its template reads",
+};
+
+pub static DUPLICATE_ATTRIBUTE: Lint = Lint {
+    code: &codes::DUPLICATE_ATTRIBUTE,
+    default_level: LintLevel::Warn,
+    severity: DiagnosticSeverity::Warning,
+    description: "an element sets the same attribute twice",
+};
+
+pub static EMPTY_TEXT: Lint = Lint {
+    code: &codes::EMPTY_TEXT,
+    default_level: LintLevel::Warn,
+    severity: DiagnosticSeverity::Warning,
+    description: "a text element has no value to display",
+};
+
+pub static INVALID_TEXT_CHILD: Lint = Lint {
+    code: &codes::INVALID_TEXT_CHILD,
+    default_level: LintLevel::Warn,
+    severity: DiagnosticSeverity::Warning,
+    description: "a text element has children other than spans",
+};
+
+pub static MIXED_LIST: Lint = Lint {
+    code: &codes::MIXED_LIST,
+    default_level: LintLevel::Warn,
+    severity: DiagnosticSeverity::Warning,
+    description: "a list contains elements of different types",
+};
+
+pub static LOW_CONTRAST: Lint = Lint {
+    code: &codes::LOW_CONTRAST,
+    default_level: LintLevel::Warn,
+    severity: DiagnosticSeverity::Warning,
+    description: "text is drawn with too little contrast against its background",
+};
+
+pub static COLOR_DEPTH: Lint = Lint {
+    code: &codes::COLOR_DEPTH,
+    default_level: LintLevel::Warn,
+    severity: DiagnosticSeverity::Warning,
+    description: "a colour cannot be shown at the colour depth the project targets",
+};
+
+pub static UNKNOWN_COLOR: Lint = Lint {
+    code: &codes::UNKNOWN_COLOR,
+    default_level: LintLevel::Warn,
+    severity: DiagnosticSeverity::Warning,
+    description: "a colour is neither a hex colour nor a colour Anathema knows by name",
+};
+
+pub static UNUSED_TEMPLATE: Lint = Lint {
+    code: &codes::UNUSED_TEMPLATE,
+    default_level: LintLevel::Warn,
+    severity: DiagnosticSeverity::Warning,
+    description: "a template is neither reachable from the root template nor registered as a component",
+};
+
+pub static UNUSED_GLOBAL: Lint = Lint {
+    code: &codes::UNUSED_GLOBAL,
+    default_level: LintLevel::Warn,
+    severity: DiagnosticSeverity::Warning,
+    description: "a global is not read by any template",
+};
+
+pub static UNQUERIED_ELEMENT: Lint = Lint {
+    code: &codes::UNQUERIED_ELEMENT,
+    default_level: LintLevel::Warn,
+    severity: DiagnosticSeverity::Hint,
+    description: "an element with an id is not found by any query of its component",
+};
+
+pub static UNMATCHED_QUERY: Lint = Lint {
+    code: &codes::UNMATCHED_QUERY,
+    default_level: LintLevel::Warn,
+    severity: DiagnosticSeverity::Warning,
+    description: "an element query of the Rust sources finds no element in the templates",
+};
+
+/// Every lint, by name.
+pub static LINTS: &[&Lint] = &[
+    &CONFLICTING_GLOBALS,
+    &SHADOWING,
+    &UNUSED_LOCAL,
+    &UNKNOWN_ATTRIBUTE,
+    &MISSING_ATTRIBUTES,
+    &DUPLICATE_ATTRIBUTE,
+    &EMPTY_TEXT,
+    &INVALID_TEXT_CHILD,
+    &MIXED_LIST,
+    &LOW_CONTRAST,
+    &COLOR_DEPTH,
+    &UNKNOWN_COLOR,
+    &UNUSED_TEMPLATE,
+    &UNUSED_GLOBAL,
+    &UNQUERIED_ELEMENT,
+    &UNMATCHED_QUERY,
+];
+
+impl Lint {
+    pub fn name(&self) -> &'static str {
+        self.code.name
+    }
+}
+
+pub fn find(name: &str) -> Option<&'static Lint> {
+    LINTS.iter().copied().find(|lint| lint.name() == name)
+}
+
+/// The level of each lint, the ones that are not set being at their default level.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LintLevels {
+    levels: HashMap<String, LintLevel>,
+}
+
+impl LintLevels {
+    pub fn new(levels: HashMap<String, LintLevel>) -> Self {
+        Self { levels }
+    }
+
+    pub fn with(mut self, lint: &Lint, level: LintLevel) -> Self {
+        self.levels.insert(lint.name().into(), level);
+        self
+    }
+
+    pub fn level(&self, lint: &Lint) -> LintLevel {
+        self.levels
+            .get(lint.name())
+            .copied()
+            .unwrap_or(lint.default_level)
+    }
+
+    /// The severity the lint reports diagnostics with, `None` if it is allowed.
+    pub fn severity(&self, lint: &Lint) -> Option<DiagnosticSeverity> {
+        match self.level(lint) {
+            LintLevel::Allow => None,
+            LintLevel::Warn => Some(lint.severity.clone()),
+            LintLevel::Deny => Some(DiagnosticSeverity::Error),
+        }
+    }
+}
+
+/// The lints allowed by the suppression comments of a template.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Suppressions {
+    /// The lints allowed in each range of the template.
+    allowed: Vec<(Location, String)>,
+    /// The names in suppression comments that are not lints.
+    pub unknown: Vec<(Location, String)>,
+}
+
+impl Suppressions {
+    /// Reads the suppression comments among the comments of a template.
+    pub fn parse(content: &str, comments: &[Location]) -> Self {
+        let mut suppressions = Self::default();
+
+        for comment in comments {
+            let text = &content[comment.to_range()];
+            let Some(directive) = text
+                .trim_start_matches('/')
+                .trim()
+                .strip_prefix(SUPPRESSION_PREFIX)
+            else {
+                continue;
+            };
+
+            let directive = directive.trim();
+            let (range, names) = if let Some(names) = directive.strip_prefix("allow-file") {
+                let file = Location {
+                    start_byte: 0,
+                    end_byte: content.len(),
+                };
+                (file, names)
+            } else if let Some(names) = directive.strip_prefix("allow") {
+                (suppressed_line(content, *comment), names)
+            } else {
+                continue;
+            };
+
+            let Some(names) = names
+                .trim()
+                .strip_prefix('(')
+                .and_then(|names| names.strip_suffix(')'))
+            else {
+                continue;
+            };
+
+            for name in names
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+            {
+                match find(name) {
+                    Some(_) => suppressions.allowed.push((range, name.into())),
+                    None => suppressions.unknown.push((*comment, name.into())),
+                }
+            }
+        }
+
+        suppressions
+    }
+
+    pub fn allows(&self, lint: &Lint, location: Location) -> bool {
+        self.allowed.iter().any(|(range, name)| {
+            name == lint.name()
+                && range.start_byte <= location.start_byte
+                && location.start_byte <= range.end_byte
+        })
+    }
+}
+
+/// The line a suppression comment applies to: its own line when it follows code, and otherwise
+/// the next line with code on it.
+fn suppressed_line(content: &str, comment: Location) -> Location {
+    let line_start = content[..comment.start_byte]
+        .rfind('\n')
+        .map_or(0, |index| index + 1);
+    if !content[line_start..comment.start_byte].trim().is_empty() {
+        return Location {
+            start_byte: line_start,
+            end_byte: comment.start_byte,
+        };
+    }
+
+    let mut start = comment.end_byte;
+    for line in content[comment.end_byte..].split_inclusive('\n') {
+        let code = line.trim();
+        if !code.is_empty() && !code.starts_with("//") {
+            let indent = line.len() - line.trim_start().len();
+            return Location {
+                start_byte: start + indent,
+                end_byte: start + line.trim_end().len(),
+            };
+        }
+        start += line.len();
+    }
+
+    Location {
+        start_byte: content.len(),
+        end_byte: content.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comments(content: &str) -> Vec<Location> {
+        let mut lexer = aml_token::Lexer::new(content);
+        lexer.by_ref().for_each(drop);
+        lexer.comments().to_vec()
+    }
+
+    #[test]
+    fn test_suppressions() {
+        let content = "// aml-ls: allow(unused-local, shadowing)\n\nlet a = 1\nlet b = 2 // aml-ls: allow(mixed-list)\nlet c = 3 // aml-ls: allow(unsued-local)\n";
+        let suppressions = Suppressions::parse(content, &comments(content));
+
+        let at = |needle: &str| {
+            let start_byte = content.find(needle).unwrap();
+            Location {
+                start_byte,
+                end_byte: start_byte + needle.len(),
+            }
+        };
+        assert!(suppressions.allows(&UNUSED_LOCAL, at("a = 1")));
+        assert!(suppressions.allows(&SHADOWING, at("a = 1")));
+        assert!(!suppressions.allows(&UNUSED_LOCAL, at("b = 2")));
+        assert!(suppressions.allows(&MIXED_LIST, at("b = 2")));
+        assert!(!suppressions.allows(&MIXED_LIST, at("c = 3")));
+        assert_eq!(suppressions.unknown.len(), 1);
+        assert_eq!(suppressions.unknown[0].1, "unsued-local");
+    }
+
+    #[test]
+    fn test_lint_levels() {
+        let levels = LintLevels::default().with(&UNUSED_LOCAL, LintLevel::Deny);
+        assert_eq!(
+            levels.severity(&UNUSED_LOCAL),
+            Some(DiagnosticSeverity::Error)
+        );
+        assert_eq!(levels.severity(&SHADOWING), None);
+        assert_eq!(
+            levels.severity(&UNQUERIED_ELEMENT),
+            Some(DiagnosticSeverity::Hint)
+        );
+    }
+}
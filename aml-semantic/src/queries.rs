@@ -0,0 +1,110 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use aml_core::Location;
+
+/// An element of a template, as seen by the queries components run on their elements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateElement {
+    /// The keyword of the element, e.g. `overflow`.
+    pub tag: String,
+    /// The location of the keyword.
+    pub location: Location,
+    pub attributes: Vec<ElementAttribute>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementAttribute {
+    pub name: String,
+    /// The literal value of the attribute, `None` when it is computed.
+    pub value: Option<String>,
+}
+
+/// What a query looks elements up by.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selector {
+    /// `elements.by_tag("overflow")`
+    Tag(String),
+    /// `elements.by_attribute("id", "list")`, the value being `None` when it is not a literal.
+    Attribute { name: String, value: Option<String> },
+}
+
+impl Selector {
+    /// Whether the query can find the element. An attribute whose value is computed may hold any
+    /// value.
+    pub fn matches(&self, element: &TemplateElement) -> bool {
+        match self {
+            Selector::Tag(tag) => element.tag == *tag,
+            Selector::Attribute { name, value } => element.attributes.iter().any(|attribute| {
+                attribute.name == *name
+                    && match (value, &attribute.value) {
+                        (Some(value), Some(attribute)) => value == attribute,
+                        _ => true,
+                    }
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Selector::Tag(tag) => write!(f, "'{tag}' element"),
+            Selector::Attribute {
+                name,
+                value: Some(value),
+            } => write!(f, "element with '{name}' set to '{value}'"),
+            Selector::Attribute { name, value: None } => write!(f, "element with '{name}'"),
+        }
+    }
+}
+
+/// A query on the elements of a component, found in the Rust sources.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementQuery {
+    /// The Rust file the query is in.
+    pub file: PathBuf,
+    /// The component running the query, `None` when it cannot be told, e.g. in a free function.
+    pub component: Option<String>,
+    pub selector: Selector,
+    /// The location of the tag or of the attribute name literal.
+    pub location: Location,
+    /// The location of the attribute value literal.
+    pub value_location: Option<Location>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selector_matches() {
+        let element = TemplateElement {
+            tag: "overflow".into(),
+            location: Location::default(),
+            attributes: vec![
+                ElementAttribute {
+                    name: "id".into(),
+                    value: Some("list".into()),
+                },
+                ElementAttribute {
+                    name: "title".into(),
+                    value: None,
+                },
+            ],
+        };
+
+        let attribute = |name: &str, value: Option<&str>| Selector::Attribute {
+            name: name.into(),
+            value: value.map(Into::into),
+        };
+        assert!(Selector::Tag("overflow".into()).matches(&element));
+        assert!(!Selector::Tag("overflw".into()).matches(&element));
+        assert!(attribute("id", Some("list")).matches(&element));
+        assert!(attribute("id", None).matches(&element));
+        assert!(!attribute("id", Some("lst")).matches(&element));
+        // a computed value may be anything
+        assert!(attribute("title", Some("done")).matches(&element));
+        assert!(!attribute("name", None).matches(&element));
+    }
+}
@@ -105,21 +105,6 @@ impl ScopeAnalyzer {
         }
     }
 
-    fn declare_variable(&mut self, name: String) {
-        if let Some(scope) = self.scopes.get_mut(self.current_scope)
-            && !scope.variables.contains(&name)
-        {
-            scope.variables.push(name);
-        }
-    }
-
-    fn get_attribute_name(&self, node: &AstNode) -> Option<String> {
-        match node {
-            AstNode::Identifier { .. } => Some("attribute".to_string()),
-            _ => None,
-        }
-    }
-
     pub fn find_scope_at_position(&self, location: Location) -> Option<&ScopeInfo> {
         self.scopes.iter().find(|scope| {
             location.start_byte >= scope.range.start.start_byte
@@ -0,0 +1,306 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use aml_core::Location;
+use thiserror::Error;
+use toml::Spanned;
+
+use crate::ValueType;
+
+const STATE_EXTENSION: &str = "state.toml";
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum StateSchemaError {
+    #[error("{message}")]
+    Syntax { message: String, location: Location },
+    #[error("unknown type '{type_name}' of field '{field}'")]
+    UnknownType {
+        field: String,
+        type_name: String,
+        location: Location,
+    },
+    #[error("field '{field}' must be a type name, a table or an array with a single table")]
+    InvalidField { field: String, location: Location },
+}
+
+impl StateSchemaError {
+    /// Where the error is in the schema file.
+    pub fn location(&self) -> Location {
+        match self {
+            Self::Syntax { location, .. }
+            | Self::UnknownType { location, .. }
+            | Self::InvalidField { location, .. } => *location,
+        }
+    }
+
+    fn at(mut self, at: Location) -> Self {
+        match &mut self {
+            Self::Syntax { location, .. }
+            | Self::UnknownType { location, .. }
+            | Self::InvalidField { location, .. } => *location = at,
+        }
+        self
+    }
+}
+
+/// The fields of the state a template is rendered with, either extracted from the Rust struct
+/// backing the template or declared in a `<name>.state.toml` file next to it:
+///
+/// ```toml
+/// title = "string"
+/// count = "number"
+/// tags = "list<string>"
+/// subtitle = "string?"
+///
+/// [user]
+/// name = "string"
+///
+/// [[items]]
+/// label = "string"
+/// ```
+///
+/// Tables are records and an array with a single table is a list of records.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StateSchema {
+    pub fields: Vec<StateField>,
+    /// The file the state is declared in.
+    pub definition: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateField {
+    pub name: String,
+    pub value_type: ValueType,
+    /// The type of the field in the Rust struct, e.g. `Value<List<Item>>`.
+    pub rust_type: Option<String>,
+    /// The location of the name of the field in the file declaring it.
+    pub location: Location,
+}
+
+impl StateSchema {
+    pub fn parse(text: &str) -> Result<Self, StateSchemaError> {
+        let table = toml::from_str::<BTreeMap<Spanned<String>, Spanned<toml::Value>>>(text)
+            .map_err(|error| StateSchemaError::Syntax {
+                message: error.message().into(),
+                location: error.span().map(location).unwrap_or_default(),
+            })?;
+
+        let fields = table
+            .into_iter()
+            .map(|(name, value)| {
+                let location = location(name.span());
+                // type names are reported where they are, anything else on the field
+                let error_location = match value.get_ref() {
+                    toml::Value::String(_) => self::location(value.span()),
+                    _ => location,
+                };
+                let value_type = parse_field(value.get_ref(), name.get_ref().clone())
+                    .map_err(|error| error.at(error_location))?;
+
+                Ok(StateField {
+                    name: name.into_inner(),
+                    value_type,
+                    rust_type: None,
+                    location,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            fields,
+            definition: None,
+        })
+    }
+
+    pub fn with_definition(mut self, definition: PathBuf) -> Self {
+        self.definition = Some(definition);
+        self
+    }
+
+    /// The schema file of a template, `templates/foo.aml` has `templates/foo.state.toml`.
+    pub fn path_for(template: &Path) -> PathBuf {
+        template.with_extension(STATE_EXTENSION)
+    }
+
+    /// The template a schema file belongs to, or `None` if the file is not a schema.
+    pub fn template_for(schema: &Path) -> Option<PathBuf> {
+        let name = schema.file_name()?.to_str()?;
+        let stem = name.strip_suffix(STATE_EXTENSION)?.strip_suffix('.')?;
+        Some(schema.with_file_name(format!("{stem}.aml")))
+    }
+
+    pub fn field(&self, name: &str) -> Option<&StateField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+fn location(span: std::ops::Range<usize>) -> Location {
+    Location::new(span.start, span.end)
+}
+
+fn parse_table(
+    table: &toml::Table,
+    path: &str,
+) -> Result<Vec<(String, ValueType)>, StateSchemaError> {
+    table
+        .iter()
+        .map(|(name, value)| {
+            let field = match path {
+                "" => name.clone(),
+                path => format!("{path}.{name}"),
+            };
+            Ok((name.clone(), parse_field(value, field)?))
+        })
+        .collect()
+}
+
+fn parse_field(value: &toml::Value, field: String) -> Result<ValueType, StateSchemaError> {
+    match value {
+        toml::Value::String(type_name) => {
+            parse_type(type_name).ok_or_else(|| StateSchemaError::UnknownType {
+                field,
+                type_name: type_name.clone(),
+                location: Location::default(),
+            })
+        }
+        toml::Value::Table(table) => Ok(ValueType::Record(parse_table(table, &field)?)),
+        toml::Value::Array(items) => match items.as_slice() {
+            [toml::Value::Table(table)] => Ok(ValueType::List(Box::new(ValueType::Record(
+                parse_table(table, &field)?,
+            )))),
+            _ => Err(StateSchemaError::InvalidField {
+                field,
+                location: Location::default(),
+            }),
+        },
+        _ => Err(StateSchemaError::InvalidField {
+            field,
+            location: Location::default(),
+        }),
+    }
+}
+
+/// Parses a type name: `string`, `number`, `bool`, `hex`, `any`, `list<T>`, `map<K, V>`, or any
+/// of them followed by `?` to make it optional.
+fn parse_type(type_name: &str) -> Option<ValueType> {
+    let type_name = type_name.trim();
+
+    if let Some(inner) = type_name.strip_suffix('?') {
+        return Some(parse_type(inner)?.optional());
+    }
+
+    if let Some(element) = generic_arguments(type_name, "list") {
+        return Some(ValueType::List(Box::new(parse_type(element)?)));
+    }
+
+    if let Some(arguments) = generic_arguments(type_name, "map") {
+        let (key, value) = split_arguments(arguments)?;
+        return Some(ValueType::Map(
+            Box::new(parse_type(key)?),
+            Box::new(parse_type(value)?),
+        ));
+    }
+
+    match type_name {
+        "string" => Some(ValueType::String),
+        "number" => Some(ValueType::Number),
+        "bool" => Some(ValueType::Boolean),
+        "hex" => Some(ValueType::Hex),
+        "any" => Some(ValueType::Unknown),
+        _ => None,
+    }
+}
+
+/// Returns the `T` of `name<T>`.
+fn generic_arguments<'a>(type_name: &'a str, name: &str) -> Option<&'a str> {
+    type_name
+        .strip_prefix(name)?
+        .trim_start()
+        .strip_prefix('<')?
+        .strip_suffix('>')
+}
+
+/// Splits `K, V` at the comma that is not nested in another type.
+fn split_arguments(arguments: &str) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    for (index, char) in arguments.char_indices() {
+        match char {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => return Some((&arguments[..index], &arguments[index + 1..])),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_schema() {
+        let schema = StateSchema::parse(
+            r#"
+            title = "string"
+            scores = "map<string, list<number>>"
+            subtitle = "string?"
+
+            [user]
+            name = "string"
+
+            [[items]]
+            done = "bool"
+            "#,
+        )
+        .unwrap();
+
+        let field_type = |name| schema.field(name).unwrap().value_type.to_string();
+        assert_eq!(field_type("title"), "string");
+        assert_eq!(field_type("scores"), "map<string, list<number>>");
+        assert_eq!(field_type("subtitle"), "string | null");
+        assert_eq!(field_type("user"), "{name: string}");
+        assert_eq!(field_type("items"), "list<{done: bool}>");
+    }
+
+    #[test]
+    fn test_schema_errors() {
+        assert_eq!(
+            StateSchema::parse("[user]\nname = \"strng\""),
+            Err(StateSchemaError::UnknownType {
+                field: "user.name".into(),
+                type_name: "strng".into(),
+                location: Location::new(1, 5),
+            })
+        );
+        assert_eq!(
+            StateSchema::parse("title = \"string\"\ncount = \"int\""),
+            Err(StateSchemaError::UnknownType {
+                field: "count".into(),
+                type_name: "int".into(),
+                location: Location::new(25, 30),
+            })
+        );
+        assert_eq!(
+            StateSchema::parse("count = 1"),
+            Err(StateSchemaError::InvalidField {
+                field: "count".into(),
+                location: Location::new(0, 5),
+            })
+        );
+        assert!(matches!(
+            StateSchema::parse("count = "),
+            Err(StateSchemaError::Syntax { .. })
+        ));
+    }
+
+    #[test]
+    fn test_schema_path() {
+        let template = Path::new("templates/foo.aml");
+        let schema = StateSchema::path_for(template);
+        assert_eq!(schema, Path::new("templates/foo.state.toml"));
+        assert_eq!(StateSchema::template_for(&schema).unwrap(), template);
+        assert!(StateSchema::template_for(Path::new("aml_ls.toml")).is_none());
+    }
+}
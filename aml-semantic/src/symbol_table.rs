@@ -1,22 +1,62 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 use aml_core::Location;
 use serde::Serialize;
 
+use crate::{StateSchema, Value};
+
 #[derive(Debug, Clone, Serialize)]
 pub struct Symbol {
     pub name: String,
     pub location: Location,
     pub symbol_type: SymbolType,
     pub scope_id: usize,
+    /// The value of the symbol, if it can be evaluated during analysis.
+    pub value: Option<Value>,
+}
+
+/// A read of an identifier, along with the declaration it resolved to.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Reference {
+    pub name: String,
+    pub location: Location,
+    /// The scope the identifier is read in.
+    pub scope_id: usize,
+    pub target: ReferenceTarget,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, Hash)]
+pub enum ReferenceTarget {
+    /// A local variable or loop binding of the file, by the location of its declaration.
+    Local(Location),
+    /// A global, by the file declaring it. The name of the reference is the name of the global,
+    /// which is unique within a file, so references outlive edits moving the declaration.
+    Global { definition: PathBuf },
+    /// The identifier is not declared anywhere.
+    Unresolved,
 }
 
 #[derive(Debug, Clone, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum SymbolType {
     Variable(ValueType),
+    LoopBinding(ValueType),
+    /// A field of the state the template is rendered with, declared in its [`StateSchema`].
+    State(ValueType),
     Element,
 }
 
+impl SymbolType {
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            SymbolType::Variable(value_type) => value_type.clone(),
+            SymbolType::LoopBinding(value_type) => value_type.clone(),
+            SymbolType::State(value_type) => value_type.clone(),
+            SymbolType::Element => ValueType::Unknown,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Hash)]
 pub enum ValueType {
     String,
@@ -24,13 +64,127 @@ pub enum ValueType {
     Boolean,
     List(Box<ValueType>),
     Map(Box<ValueType>, Box<ValueType>),
+    /// A map literal whose keys are all known, e.g. `{width: 10, title: "x"}`.
+    Record(Vec<(String, ValueType)>),
     Hex,
+    /// The absence of a value, e.g. an index past the end of a list.
+    Null,
+    /// One of several types, built with [`ValueType::union`]. An optional type is a union with
+    /// [`ValueType::Null`].
+    Union(Vec<ValueType>),
     Unknown,
 }
 
-#[derive(Debug, Default)]
+impl ValueType {
+    /// Combines types into a flat union without duplicates. A single type is returned as is, and
+    /// an unknown member makes the whole union unknown.
+    pub fn union(types: impl IntoIterator<Item = ValueType>) -> ValueType {
+        let mut members = Vec::new();
+        for value_type in types {
+            match value_type {
+                ValueType::Unknown => return ValueType::Unknown,
+                ValueType::Union(inner) => members.extend(inner),
+                value_type => members.push(value_type),
+            }
+        }
+
+        // keep null last so optionals read as `string | null`
+        members.sort_by_key(|member| matches!(member, ValueType::Null));
+        let mut seen = Vec::with_capacity(members.len());
+        members.retain(|member| match seen.contains(member) {
+            true => false,
+            false => {
+                seen.push(member.clone());
+                true
+            }
+        });
+
+        match members.len() {
+            0 => ValueType::Unknown,
+            1 => members.remove(0),
+            _ => ValueType::Union(members),
+        }
+    }
+
+    pub fn optional(self) -> ValueType {
+        ValueType::union([self, ValueType::Null])
+    }
+
+    /// Returns the type without its null part, e.g. `string` for `string | null`.
+    pub fn without_null(self) -> ValueType {
+        match self {
+            ValueType::Union(members) => ValueType::union(
+                members
+                    .into_iter()
+                    .filter(|member| *member != ValueType::Null),
+            ),
+            value_type => value_type,
+        }
+    }
+
+    /// The members of a union, or the type itself.
+    pub fn members(&self) -> &[ValueType] {
+        match self {
+            ValueType::Union(members) => members,
+            value_type => std::slice::from_ref(value_type),
+        }
+    }
+
+    /// Returns the type of a record field, or `None` if this is a record without that field.
+    pub fn field(&self, name: &str) -> Option<&ValueType> {
+        match self {
+            ValueType::Record(fields) => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value_type)| value_type),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValueType::String => write!(f, "string"),
+            ValueType::Number => write!(f, "number"),
+            ValueType::Boolean => write!(f, "bool"),
+            ValueType::List(element) => write!(f, "list<{element}>"),
+            ValueType::Map(key, value) => write!(f, "map<{key}, {value}>"),
+            ValueType::Record(fields) => {
+                write!(f, "{{")?;
+                for (index, (name, value_type)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{name}: {value_type}")?;
+                }
+                write!(f, "}}")
+            }
+            ValueType::Hex => write!(f, "hex"),
+            ValueType::Null => write!(f, "null"),
+            ValueType::Union(members) => {
+                for (index, member) in members.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, " | ")?;
+                    }
+                    write!(f, "{member}")?;
+                }
+                Ok(())
+            }
+            ValueType::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct SymbolTable {
     symbols: HashMap<String, Vec<Symbol>>,
+    /// Every identifier read, in the order they were analyzed.
+    references: Vec<Reference>,
+    /// The locations of `references`, a location being read once.
+    read_locations: HashSet<Location>,
+    /// The indices of `references` by what they resolved to.
+    references_by_target: HashMap<ReferenceTarget, Vec<usize>>,
     scopes: Vec<Scope>,
     current_scope: usize,
 }
@@ -46,6 +200,9 @@ impl SymbolTable {
     pub fn new() -> Self {
         let mut table = Self {
             symbols: HashMap::new(),
+            references: Vec::new(),
+            read_locations: HashSet::new(),
+            references_by_target: HashMap::new(),
             scopes: Vec::new(),
             current_scope: 0,
         };
@@ -54,6 +211,23 @@ impl SymbolTable {
         table
     }
 
+    /// Creates a table whose root scope holds the fields of the template state, the template
+    /// itself being analyzed in a child scope so its variables can shadow them.
+    pub fn with_state(schema: &StateSchema) -> Self {
+        let mut table = Self::new();
+        for field in &schema.fields {
+            table.declare_symbol(
+                field.name.clone(),
+                field.location,
+                SymbolType::State(field.value_type.clone()),
+                None,
+            );
+        }
+
+        table.push_scope(None);
+        table
+    }
+
     pub fn push_scope(&mut self, parent: Option<usize>) -> usize {
         let scope_id = self.scopes.len();
         let parent = parent.or(if scope_id == 0 { None } else { Some(self.current_scope) });
@@ -74,12 +248,19 @@ impl SymbolTable {
         }
     }
 
-    pub fn declare_symbol(&mut self, name: String, location: Location, symbol_type: SymbolType) {
+    pub fn declare_symbol(
+        &mut self,
+        name: String,
+        location: Location,
+        symbol_type: SymbolType,
+        value: Option<Value>,
+    ) {
         let symbol = Symbol {
             name: name.clone(),
             location,
             symbol_type,
             scope_id: self.current_scope,
+            value,
         };
 
         self.scopes[self.current_scope].symbols.push(name.clone());
@@ -87,14 +268,78 @@ impl SymbolTable {
     }
 
     pub fn lookup_symbol(&self, name: &str) -> Option<&Symbol> {
+        let index = self.find_visible_symbol(name)?;
+        Some(&self.symbols[name][index])
+    }
+
+    /// Records a read of an identifier. Reading the same location twice is recorded once.
+    pub fn add_reference(&mut self, reference: Reference) {
+        if !self.read_locations.insert(reference.location) {
+            return;
+        }
+
+        self.references_by_target
+            .entry(reference.target.clone())
+            .or_default()
+            .push(self.references.len());
+        self.references.push(reference);
+    }
+
+    pub fn references(&self) -> &[Reference] {
+        &self.references
+    }
+
+    /// Returns the reference whose identifier contains the byte offset.
+    pub fn reference_at(&self, byte_offset: usize) -> Option<&Reference> {
+        self.references
+            .iter()
+            .find(|reference| reference.location.contains(byte_offset))
+    }
+
+    /// Returns every read of a local symbol.
+    pub fn references_to<'a>(&'a self, symbol: &'a Symbol) -> impl Iterator<Item = &'a Reference> {
+        self.references_with_target(ReferenceTarget::Local(symbol.location))
+            .filter(|reference| reference.name == symbol.name)
+    }
+
+    /// Returns every read of the global `name` declared in `definition`.
+    pub fn references_to_global<'a>(
+        &'a self,
+        definition: &Path,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a Reference> {
+        self.references_with_target(ReferenceTarget::Global {
+            definition: definition.into(),
+        })
+        .filter(move |reference| reference.name == name)
+    }
+
+    fn references_with_target(&self, target: ReferenceTarget) -> impl Iterator<Item = &Reference> {
+        self.references_by_target
+            .get(&target)
+            .into_iter()
+            .flatten()
+            .map(|index| &self.references[*index])
+    }
+
+    /// Returns the local symbol a reference resolved to.
+    pub fn resolve(&self, reference: &Reference) -> Option<&Symbol> {
+        let ReferenceTarget::Local(location) = reference.target else { return None };
+        self.symbols
+            .get(&reference.name)?
+            .iter()
+            .find(|symbol| symbol.location == location)
+    }
+
+    fn find_visible_symbol(&self, name: &str) -> Option<usize> {
         let symbols = self.symbols.get(name)?;
 
         let mut scope_id = Some(self.current_scope);
         while let Some(id) = scope_id {
             // we can shadow variables in the same scope, so when finding a symbol, we need to
             // return the last one we find, since it's the most recently declared one
-            if let Some(symbol) = symbols.iter().rev().find(|s| s.scope_id == id) {
-                return Some(symbol);
+            if let Some(index) = symbols.iter().rposition(|s| s.scope_id == id) {
+                return Some(index);
             }
             scope_id = self.scopes[id].parent;
         }
@@ -102,6 +347,25 @@ impl SymbolTable {
         None
     }
 
+    pub fn unused_symbols(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols
+            .values()
+            .flatten()
+            .filter(|symbol| self.references_to(symbol).next().is_none())
+    }
+
+    /// The fields of the template state, sorted by name.
+    pub fn state_fields(&self) -> Vec<&Symbol> {
+        let mut fields = self
+            .symbols
+            .values()
+            .flatten()
+            .filter(|symbol| matches!(symbol.symbol_type, SymbolType::State(_)))
+            .collect::<Vec<_>>();
+        fields.sort_by(|a, b| a.name.cmp(&b.name));
+        fields
+    }
+
     pub fn get_symbols_in_scope(&self, scope_id: usize) -> Vec<&Symbol> {
         let mut result = Vec::new();
 
@@ -118,6 +382,7 @@ impl SymbolTable {
         result
     }
 
+    /// Returns every declaration of `name`, see [`SymbolTable::references`] for its uses.
     pub fn find_references(&self, name: &str) -> Vec<&Symbol> {
         self.symbols
             .get(name)
@@ -0,0 +1,188 @@
+//! The syntax errors of a template. The lexer and the parser do not stop at the first error, they
+//! leave error nodes in the ast, which are reported here.
+
+use aml_core::codes::{INVALID_HEX, UNEXPECTED_TOKEN, UNTERMINATED_STRING};
+use aml_core::{Diagnostic, Location};
+use aml_token::{LexError, TokenKind};
+
+use crate::ast::*;
+
+/// Reports every error node of the ast, in the order they appear in the template.
+pub fn syntax_diagnostics(ast: &Ast, content: &str) -> Vec<Diagnostic> {
+    let mut collector = ErrorCollector {
+        content,
+        diagnostics: vec![],
+    };
+    for node in ast.nodes.iter() {
+        collector.node(node);
+    }
+
+    collector.diagnostics
+}
+
+struct ErrorCollector<'src> {
+    content: &'src str,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ErrorCollector<'_> {
+    fn node(&mut self, node: &AstNode) {
+        match node {
+            AstNode::Error(error) => self.error(error.token, error.location),
+            AstNode::Component(component) => {
+                self.node(&component.name);
+                self.nodes(&component.attributes.items);
+            }
+            AstNode::ComponentSlot(slot) => self.node(&slot.name),
+            AstNode::Container(container) => {
+                self.nodes(&container.attributes.items);
+                self.nodes(&container.children);
+            }
+            AstNode::Text(text) => {
+                self.nodes(&text.attributes.items);
+                // other values that cannot be displayed are reported by the analysis, which knows
+                // the value of a text element is not an expression
+                for value in text.values.iter() {
+                    if let AstNode::Error(error) = value
+                        && matches!(error.token, TokenKind::Error(_))
+                    {
+                        self.error(error.token, error.location);
+                    }
+                }
+                self.nodes(&text.children);
+            }
+            AstNode::Span(span) => {
+                self.nodes(&span.attributes.items);
+                self.nodes(&span.values);
+            }
+            AstNode::Attribute(attribute) => {
+                // keywords are valid attribute names, e.g. `padding` in `text [padding: 1]`, even
+                // though the parser leaves them as error nodes
+                match &*attribute.name {
+                    AstNode::Error(error) if is_keyword(error.token) => {}
+                    name => self.node(name),
+                }
+                self.expr(&attribute.value);
+            }
+            AstNode::Declaration(declaration) => {
+                self.node(&declaration.name);
+                self.expr(&declaration.value);
+            }
+            AstNode::For(for_loop) => {
+                self.node(&for_loop.binding);
+                self.expr(&for_loop.value);
+                self.nodes(&for_loop.children);
+            }
+            AstNode::String(_) | AstNode::Primitive(_) | AstNode::Identifier(_) => {}
+        }
+    }
+
+    fn nodes(&mut self, nodes: &[AstNode]) {
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Error(error) => self.error(error.token, error.location),
+            Expr::Unary(unary) => self.expr(&unary.expr),
+            Expr::Binary(binary) => {
+                self.expr(&binary.lhs);
+                self.expr(&binary.rhs);
+            }
+            Expr::Call(call) => {
+                self.expr(&call.fun);
+                call.args.iter().for_each(|arg| self.expr(arg));
+            }
+            Expr::ArrayIndex(index) => {
+                self.expr(&index.lhs);
+                self.expr(&index.index);
+            }
+            Expr::List(list) => list.items.iter().for_each(|item| self.expr(item)),
+            Expr::Map(map) => {
+                for (key, value) in map.items.iter() {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            Expr::Ident(_) | Expr::String(_) | Expr::Primitive(_) => {}
+        }
+    }
+
+    fn error(&mut self, token: TokenKind, location: Location) {
+        let diagnostic = match token {
+            TokenKind::Error(LexError::UnterminatedString) => {
+                Diagnostic::error(location, "unterminated string")
+                    .with_code(UNTERMINATED_STRING.code)
+            }
+            TokenKind::Error(LexError::InvalidHex) => {
+                Diagnostic::error(location, "invalid hex colour, expected 3 or 6 hex digits")
+                    .with_code(INVALID_HEX.code)
+            }
+            TokenKind::Eof => Diagnostic::error(location, "unexpected end of file")
+                .with_code(UNEXPECTED_TOKEN.code),
+            TokenKind::Newline => Diagnostic::error(location, "unexpected end of line")
+                .with_code(UNEXPECTED_TOKEN.code),
+            _ => {
+                let text = self.content.get(location.to_range()).unwrap_or_default();
+                Diagnostic::error(location, format!("unexpected '{text}'"))
+                    .with_code(UNEXPECTED_TOKEN.code)
+            }
+        };
+
+        self.diagnostics.push(diagnostic);
+    }
+}
+
+fn is_keyword(token: TokenKind) -> bool {
+    matches!(
+        token,
+        TokenKind::For
+            | TokenKind::In
+            | TokenKind::If
+            | TokenKind::Else
+            | TokenKind::Switch
+            | TokenKind::Case
+            | TokenKind::Default
+            | TokenKind::With
+            | TokenKind::As
+            | TokenKind::Decl
+            | TokenKind::Global
+            | TokenKind::Local
+            | TokenKind::Element(_)
+            | TokenKind::Container(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use aml_token::{Lexer, Tokens};
+
+    use super::*;
+    use crate::Parser;
+
+    #[test]
+    fn test_syntax_diagnostics() {
+        let template =
+            "text [foreground: #ff00, padding: 1] \"ok\" +\nvstack [width: 1 + )]\ntext \"open";
+        let tokens = Tokens::new(Lexer::new(template).collect(), template.len());
+        let ast = Parser::new(tokens).parse();
+
+        let diagnostics = syntax_diagnostics(&ast, template)
+            .into_iter()
+            .map(|diagnostic| (diagnostic.code.unwrap(), diagnostic.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            diagnostics,
+            vec![
+                (
+                    "AML0102".to_string(),
+                    "invalid hex colour, expected 3 or 6 hex digits".to_string()
+                ),
+                ("AML0103".to_string(), "unexpected ')'".to_string()),
+                ("AML0101".to_string(), "unterminated string".to_string()),
+            ]
+        );
+    }
+}
@@ -1,9 +1,11 @@
 pub mod ast;
+pub mod diagnostics;
 pub mod expressions;
 pub mod parser;
 
 use ast::*;
 pub use ast::{Ast, Scope};
+pub use diagnostics::syntax_diagnostics;
 pub use parser::Parser;
 
 #[derive(Debug, PartialEq, PartialOrd)]
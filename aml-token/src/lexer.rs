@@ -1,12 +1,15 @@
 use std::iter::Peekable;
 use std::str::CharIndices;
 
+use aml_core::Location;
+
 use crate::Container;
 use crate::token::{Element, IntoToken, LexError, Operator, Primitive, Token, TokenKind};
 
 pub struct Lexer<'lex> {
     chars: Peekable<CharIndices<'lex>>,
     content: &'lex str,
+    comments: Vec<Location>,
 }
 
 impl<'lex> Lexer<'lex> {
@@ -14,9 +17,16 @@ impl<'lex> Lexer<'lex> {
         Self {
             chars: content.char_indices().peekable(),
             content,
+            comments: Vec::new(),
         }
     }
 
+    /// The comments lexed so far, which are not part of the tokens. Lex with `by_ref` to read
+    /// them afterwards.
+    pub fn comments(&self) -> &[Location] {
+        &self.comments
+    }
+
     fn next_token(&mut self) -> Token {
         let (index, curr) = match self.chars.next() {
             None => return self.eof(),
@@ -28,12 +38,17 @@ impl<'lex> Lexer<'lex> {
         match (curr, next) {
             ('/', Some('/')) => {
                 self.chars.next(); // consume the second slash
-                loop {
-                    if let Some((_, '\n')) | None = self.chars.peek() {
-                        break;
-                    };
-                    self.chars.next();
-                }
+                let end_byte = loop {
+                    match self.chars.peek() {
+                        Some((end_byte, '\n')) => break *end_byte,
+                        None => break self.content.len(),
+                        _ => _ = self.chars.next(),
+                    }
+                };
+                self.comments.push(Location {
+                    start_byte: index,
+                    end_byte,
+                });
                 self.next_token()
             }
             ('&', Some('&')) => {
@@ -192,6 +207,7 @@ impl<'lex> Lexer<'lex> {
                     break TokenKind::String(location).into_token(start_byte, end_byte);
                 }
                 // Handle escape sequences
+                #[allow(clippy::collapsible_match)]
                 Some((_, '\\')) => {
                     // found escape character, so consume the next character regardless of what it
                     // is. This properly handles \", \', \\, \n, etc.
@@ -281,7 +297,6 @@ impl Iterator for Lexer<'_> {
 
 #[cfg(test)]
 mod tests {
-    use aml_core::Location;
     use serde::Serialize;
 
     use super::*;
@@ -321,6 +336,20 @@ vstack        // 0 spaces
         insta::assert_yaml_snapshot!(tokens);
     }
 
+    #[test]
+    fn test_comments() {
+        let template = "// header\ntext \"// not a comment\" // trailing";
+        let mut lexer = Lexer::new(template);
+        lexer.by_ref().for_each(drop);
+
+        let comments = lexer
+            .comments()
+            .iter()
+            .map(|comment| &template[comment.to_range()])
+            .collect::<Vec<_>>();
+        assert_eq!(comments, vec!["// header", "// trailing"]);
+    }
+
     #[test]
     fn test_attributes() {
         let template = r#"
@@ -142,12 +142,24 @@ pub struct Hex {
     b: u8,
 }
 
+impl Hex {
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        (self.r, self.g, self.b)
+    }
+}
+
 impl From<(u8, u8, u8)> for Hex {
     fn from((r, g, b): (u8, u8, u8)) -> Self {
         Self { r, g, b }
     }
 }
 
+impl std::fmt::Display for Hex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, PartialEq, PartialOrd)]
 pub struct Token(pub TokenKind, pub Location);
 
@@ -1,16 +1,19 @@
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use aml_rust::{RustWorkspace, is_rust_file};
 use aml_semantic::global_scope::{GlobalScope, GlobalSymbol};
-use aml_semantic::{SemanticAnalyzer, SemanticInfo, Symbol};
+use aml_semantic::{
+    AnalyzerOptions, ComponentFiles, Database, FileDiagnostics, SemanticDiagnostic, SemanticInfo,
+    Settings, StateSchema, Symbol, SymbolType,
+};
 use aml_syntax::ast::*;
 use aml_syntax::{Ast, NodeFinder, NodeFinderResult, Parser};
 use aml_token::{Lexer, Tokens};
 use tokio::sync::RwLock;
 use tower_lsp::lsp_types::*;
 
-use crate::core::template_service::Templates;
-
 pub fn parse_content(content: &str) -> Ast {
     let tokens = Lexer::new(content).collect();
     let tokens = Tokens::new(tokens, content.len());
@@ -20,13 +23,18 @@ pub fn parse_content(content: &str) -> Ast {
 #[derive(Debug)]
 pub struct FileInfo {
     pub content: String,
-    pub ast: Ast,
-    pub semantic_info: SemanticInfo,
+    pub ast: Arc<Ast>,
+    pub semantic_info: Arc<SemanticInfo>,
     pub version: i32,
 }
 
 impl FileInfo {
-    pub fn new(content: String, ast: Ast, semantic_info: SemanticInfo, version: i32) -> Self {
+    pub fn new(
+        content: String,
+        ast: Arc<Ast>,
+        semantic_info: Arc<SemanticInfo>,
+        version: i32,
+    ) -> Self {
         Self {
             ast,
             content,
@@ -36,9 +44,22 @@ impl FileInfo {
     }
 }
 
+/// Keeps the loaded templates in sync with the editor. The analysis itself lives in the
+/// [`Database`], the files only hold the latest results so features can read them.
 #[derive(Debug, Default)]
 pub struct DocumentManager {
     files: Arc<RwLock<HashMap<Url, FileInfo>>>,
+    /// The content of the documents open in the editor that templates depend on.
+    sources: HashMap<Url, String>,
+    rust: RustWorkspace,
+    /// The diagnostics that take the whole workspace to compute: the element queries of the Rust
+    /// files, and the templates and globals nothing uses.
+    workspace_diagnostics: HashMap<Url, Vec<SemanticDiagnostic>>,
+    /// The diagnostics of the configuration of the project.
+    config_diagnostics: FileDiagnostics,
+    db: Database,
+    settings: Settings,
+    global_scope: Arc<GlobalScope>,
 }
 
 impl DocumentManager {
@@ -46,16 +67,93 @@ impl DocumentManager {
         &self.files
     }
 
-    pub async fn add_or_update_file(
-        &self,
-        global_scope: &mut GlobalScope,
-        uri: Url,
-        ast: Ast,
-        content: String,
-        version: i32,
-    ) {
-        let mut analyzer = SemanticAnalyzer::new(&content, global_scope);
-        let semantic_info = analyzer.analyze(&ast);
+    pub fn set_analyzer_options(&mut self, options: AnalyzerOptions) {
+        self.settings.options = options;
+        self.db.set_settings(self.settings.clone());
+    }
+
+    pub fn set_templates_dir(&mut self, templates_dir: PathBuf) {
+        self.settings.components.templates_dir = Some(templates_dir);
+        self.db.set_settings(self.settings.clone());
+    }
+
+    pub fn set_root_template(&mut self, root_template: Option<PathBuf>) {
+        self.settings.root_template = root_template;
+        self.db.set_settings(self.settings.clone());
+    }
+
+    /// Sets the diagnostics of the configuration, published along with the workspace diagnostics.
+    pub fn set_config_diagnostics(&mut self, diagnostics: FileDiagnostics) {
+        self.config_diagnostics = diagnostics;
+    }
+
+    /// The globals of every template along with the interface of every component.
+    pub fn global_scope(&self) -> &GlobalScope {
+        &self.global_scope
+    }
+
+    /// Where the template of each component lives, see [`ComponentFiles`].
+    pub fn components(&self) -> &ComponentFiles {
+        &self.settings.components
+    }
+
+    /// The content of a document open in the editor that is not a template.
+    pub fn source(&self, uri: &Url) -> Option<&str> {
+        self.sources.get(uri).map(String::as_str)
+    }
+
+    /// The content of a loaded template or state schema.
+    pub fn text(&self, path: &Path) -> Option<Arc<str>> {
+        match StateSchema::template_for(path) {
+            Some(template) => self.db.state_text(&template),
+            None => self.db.file_text(path),
+        }
+    }
+
+    pub fn workspace_diagnostics(&self, uri: &Url) -> &[SemanticDiagnostic] {
+        self.workspace_diagnostics
+            .get(uri)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The files with workspace diagnostics, see [`DocumentManager::workspace_diagnostics`].
+    pub fn workspace_diagnostic_uris(&self) -> impl Iterator<Item = &Url> {
+        self.workspace_diagnostics.keys()
+    }
+
+    /// Loads the Rust sources of the project, which templates get the type of their state from.
+    pub fn load_rust_sources(&mut self, root_dir: &Path) {
+        self.rust = RustWorkspace::load(root_dir);
+        self.update_rust_workspace();
+    }
+
+    /// Picks up the components registered in the Rust sources, their state and their element
+    /// queries.
+    fn update_rust_workspace(&mut self) {
+        self.settings.components.registered = self.rust.components();
+        self.db.set_settings(self.settings.clone());
+        self.db.set_rust_states(self.rust.states());
+        self.db.set_element_queries(self.rust.element_queries());
+    }
+
+    /// Loads a template that is not open in the editor, e.g. when preloading the workspace. Call
+    /// [`DocumentManager::refresh`] once all of them are loaded.
+    pub async fn add_or_update_file(&mut self, uri: Url, content: String, version: i32) {
+        let path = file_path(&uri);
+        self.db.set_file_text(&path, content.as_str());
+
+        // the schema is read from disk unless it is open in the editor
+        let state = StateSchema::path_for(&path);
+        let state_open =
+            Url::from_file_path(&state).is_ok_and(|uri| self.sources.contains_key(&uri));
+        if !state_open {
+            let state = std::fs::read_to_string(state).ok();
+            self.db.set_state_text(&path, state.map(Into::into));
+        }
+
+        let ast = self.db.ast(&path).unwrap_or_default();
+        let semantic_info = self.db.semantic_info(&path).unwrap_or_default();
         let mut files = self.files.write().await;
 
         if let Some(file) = files.get_mut(&uri) {
@@ -69,67 +167,150 @@ impl DocumentManager {
         }
     }
 
-    // TODO(wiru): we will already have registered any component referenced by index so we really
-    // should update it here if necessary and not assume we can insert as a new one
-    //
     // TODO(wiru): in the future, we will watch referenced files for changes in order to update
     // the internal state properly. The LSP specification states that once we receive a did_open
     // event, the server should never read directly from the file, but instead use synchronization
     // events to keep its state up to date. That means we will need to track when files are open
     // to remove watchers, and when they are closed to add them back
-    pub async fn did_open(
-        &self,
-        params: DidOpenTextDocumentParams,
-        globals: &mut GlobalScope,
-        _templates: &mut Templates,
-    ) {
-        let uri = params.text_document.uri.clone();
+    /// Returns the other files whose analysis changed as a result of opening this one.
+    pub async fn did_open(&mut self, params: DidOpenTextDocumentParams) -> Vec<Url> {
+        let uri = params.text_document.uri;
         let content = params.text_document.text;
         let version = params.text_document.version;
-        let ast = parse_content(&content);
-        self.add_or_update_file(globals, uri, ast, content, version)
-            .await;
+
+        if let Some(source) = Source::of(&uri) {
+            self.update_source(&source, Some(&content));
+            self.sources.insert(uri, content);
+            return self.refresh().await;
+        }
+
+        self.add_or_update_file(uri.clone(), content, version).await;
+        self.refresh_dependents(&uri).await
     }
 
-    pub async fn did_change(
-        &self,
-        params: DidChangeTextDocumentParams,
-        global_scope: &mut GlobalScope,
-    ) {
+    /// Returns the other files whose analysis changed as a result of this change.
+    pub async fn did_change(&mut self, params: DidChangeTextDocumentParams) -> Vec<Url> {
         let uri = params.text_document.uri;
+
+        if let Some(source) = Source::of(&uri) {
+            let Some(content) = self.sources.get_mut(&uri) else { return vec![] };
+            apply_changes(content, params.content_changes);
+            let content = content.clone();
+            self.update_source(&source, Some(&content));
+            return self.refresh().await;
+        }
+
         let mut files = self.files.write().await;
         let file = files.get_mut(&uri);
 
-        let Some(file) = file else { return };
-        for change in params.content_changes {
-            if let Some(range) = change.range {
-                // this is an incremental update. We apply the change to our stored content.
-                let start = DocumentManager::position_to_byte_offset(&file.content, range.start);
-                let end = DocumentManager::position_to_byte_offset(&file.content, range.end);
-                file.content.replace_range(start..end, &change.text);
-            } else {
-                // this is a full update. The client has sent the entire document content.
-                file.content = change.text;
+        let Some(file) = file else { return vec![] };
+        apply_changes(&mut file.content, params.content_changes);
+        file.version = params.text_document.version;
+        self.db
+            .set_file_text(&file_path(&uri), file.content.as_str());
+        drop(files);
+
+        self.refresh_dependents(&uri).await
+    }
+
+    /// Returns the other files whose analysis changed as a result of closing this one.
+    pub async fn did_close(&mut self, params: DidCloseTextDocumentParams) -> Vec<Url> {
+        let uri = params.text_document.uri;
+        self.files.write().await.remove(&uri);
+        self.sources.remove(&uri);
+
+        // unsaved changes are discarded when closing, so the file goes back to what is on disk. If
+        // the file was deleted, it is no longer part of the workspace.
+        let path = file_path(&uri);
+        let content = std::fs::read_to_string(&path).ok();
+        match (Source::of(&uri), content) {
+            (Some(source), content) => self.update_source(&source, content.as_deref()),
+            (None, Some(content)) => self.db.set_file_text(&path, content),
+            (None, None) => self.db.remove_file(&path),
+        }
+
+        self.refresh().await
+    }
+
+    /// Updates a document templates depend on, `None` meaning it no longer exists.
+    fn update_source(&mut self, source: &Source, content: Option<&str>) {
+        match source {
+            Source::State(template) => self.db.set_state_text(template, content.map(Into::into)),
+            Source::Rust(path) => {
+                match content {
+                    Some(content) => self.rust.set_file(path, content),
+                    None => self.rust.remove_file(path),
+                }
+                self.update_rust_workspace();
             }
         }
+    }
 
-        let ast = parse_content(&file.content);
-        let mut analyzer = SemanticAnalyzer::new(&file.content, global_scope);
-        let semantic_info = analyzer.analyze(&ast);
-        file.ast = ast;
-        file.semantic_info = semantic_info;
-        file.version = params.text_document.version;
+    /// Picks up the analysis of every loaded file that changed since the last refresh, returning
+    /// the files whose diagnostics have to be published again.
+    pub async fn refresh(&mut self) -> Vec<Url> {
+        let mut files = self.files.write().await;
+        let mut changed = vec![];
+
+        for (uri, file) in files.iter_mut() {
+            let path = file_path(uri);
+            let Some(semantic_info) = self.db.semantic_info(&path) else { continue };
+            if Arc::ptr_eq(&semantic_info, &file.semantic_info) {
+                continue;
+            }
+
+            file.ast = self.db.ast(&path).unwrap_or_default();
+            file.semantic_info = semantic_info;
+            changed.push(uri.clone());
+        }
+
+        self.global_scope = self.db.workspace_scope();
+        drop(files);
+
+        let mut workspace_diagnostics = HashMap::<Url, Vec<SemanticDiagnostic>>::new();
+        let diagnostics = [
+            self.db.state_schema_diagnostics(),
+            self.db.element_query_diagnostics(),
+            self.db.unused_diagnostics(),
+        ];
+        let diagnostics = diagnostics
+            .iter()
+            .flat_map(|diagnostics| diagnostics.iter())
+            .chain(&self.config_diagnostics);
+        for (path, diagnostics) in diagnostics {
+            let Ok(uri) = Url::from_file_path(path) else { continue };
+            workspace_diagnostics
+                .entry(uri)
+                .or_default()
+                .extend(diagnostics.iter().cloned());
+        }
+        for uri in self
+            .workspace_diagnostics
+            .keys()
+            .chain(workspace_diagnostics.keys())
+        {
+            if self.workspace_diagnostics.get(uri) != workspace_diagnostics.get(uri)
+                && !changed.contains(uri)
+            {
+                changed.push(uri.clone());
+            }
+        }
+        self.workspace_diagnostics = workspace_diagnostics;
+
+        changed
     }
 
-    pub async fn did_close(&self, params: DidCloseTextDocumentParams) {
-        self.files.write().await.remove(&params.text_document.uri);
+    async fn refresh_dependents(&mut self, uri: &Url) -> Vec<Url> {
+        let mut dependents = self.refresh().await;
+        dependents.retain(|dependent| dependent != uri);
+        dependents
     }
 
     pub async fn goto_definition(
         &self,
         params: GotoDefinitionParams,
-        global_scope: &GlobalScope,
     ) -> tower_lsp::jsonrpc::Result<Option<GotoDefinitionResponse>> {
+        let global_scope = &self.global_scope;
         let uri = params.text_document_position_params.text_document.uri;
         let position = params.text_document_position_params.position;
 
@@ -137,6 +318,24 @@ impl DocumentManager {
         let Some(file) = files.get(&uri) else { return Ok(None) };
         let byte_offset = DocumentManager::position_to_byte_offset(&file.content, position);
 
+        // `@name` goes to the template of the component
+        let component = file
+            .semantic_info
+            .interface
+            .components
+            .iter()
+            .find(|component| component.location.contains(byte_offset));
+        if let Some(component) = component {
+            let template = self.settings.components.file(&component.name);
+            let location = template
+                .and_then(|template| Url::from_file_path(template).ok())
+                .map(|uri| Location {
+                    uri,
+                    range: Range::default(),
+                });
+            return Ok(location.map(GotoDefinitionResponse::Scalar));
+        }
+
         let mut finder = NodeFinder {
             byte_offset,
             result: None,
@@ -145,29 +344,51 @@ impl DocumentManager {
         file.ast.accept(&mut finder);
 
         let local_symbol_location = |symbol: &Symbol| {
+            // state fields are declared in the schema or the Rust struct backing the template
+            if let SymbolType::State(_) = symbol.symbol_type {
+                let definition = file.semantic_info.state.as_ref()?.definition.as_ref()?;
+                let uri = Url::from_file_path(definition).ok()?;
+                // the schema is loaded, a Rust file is read from disk unless it is open
+                let content = match self.text(definition) {
+                    Some(content) => content.to_string(),
+                    None => match self.source(&uri) {
+                        Some(content) => content.into(),
+                        None => std::fs::read_to_string(definition).unwrap_or_default(),
+                    },
+                };
+                let start =
+                    DocumentManager::byte_offset_to_position(&content, symbol.location.start_byte);
+                let end =
+                    DocumentManager::byte_offset_to_position(&content, symbol.location.end_byte);
+                return Some(Location {
+                    uri,
+                    range: Range::new(start, end),
+                });
+            }
+
             let start =
                 DocumentManager::byte_offset_to_position(&file.content, symbol.location.start_byte);
             let end =
                 DocumentManager::byte_offset_to_position(&file.content, symbol.location.end_byte);
-            Location {
+            Some(Location {
                 uri: uri.clone(),
                 range: Range::new(start, end),
-            }
+            })
         };
 
         let global_symbol_location = |symbol: &GlobalSymbol| {
+            // globals are declared in any template of the workspace
+            let content = self.text(&symbol.definition).unwrap_or_default();
             let start =
-                DocumentManager::byte_offset_to_position(&file.content, symbol.location.start_byte);
-            let end =
-                DocumentManager::byte_offset_to_position(&file.content, symbol.location.end_byte);
-            let symbol_uri = Url::from_file_path(&symbol.definition).unwrap();
-            Location {
-                uri: symbol_uri,
+                DocumentManager::byte_offset_to_position(&content, symbol.location.start_byte);
+            let end = DocumentManager::byte_offset_to_position(&content, symbol.location.end_byte);
+            Some(Location {
+                uri: Url::from_file_path(&symbol.definition).ok()?,
                 range: Range::new(start, end),
-            }
+            })
         };
 
-        let result = match finder.result {
+        match finder.result {
             Some(NodeFinderResult::Node(node)) => {
                 let name = match node {
                     AstNode::Identifier(_) => Some(node.text(&file.content)),
@@ -178,14 +399,14 @@ impl DocumentManager {
                 let Some(name) = name else { return Ok(None) };
 
                 let global = global_scope
-                    .lookup_symbol(&name)
-                    .map(global_symbol_location);
+                    .lookup_symbol(name)
+                    .and_then(global_symbol_location);
 
                 let local = file
                     .semantic_info
                     .symbol_table
-                    .lookup_symbol(&name)
-                    .map(local_symbol_location);
+                    .lookup_symbol(name)
+                    .and_then(local_symbol_location);
 
                 match (global, local) {
                     (Some(g), Some(l)) => Ok(Some(GotoDefinitionResponse::Array(vec![g, l]))),
@@ -195,9 +416,7 @@ impl DocumentManager {
                 }
             }
             _ => Ok(None),
-        };
-
-        result
+        }
     }
 
     /// Converts a LSP position to a byte offset in the given content.
@@ -253,3 +472,43 @@ impl DocumentManager {
         }
     }
 }
+
+/// Applies the changes sent by the editor to the content of a document.
+fn apply_changes(content: &mut String, changes: Vec<TextDocumentContentChangeEvent>) {
+    for change in changes {
+        if let Some(range) = change.range {
+            // this is an incremental update. We apply the change to our stored content.
+            let start = DocumentManager::position_to_byte_offset(content, range.start);
+            let end = DocumentManager::position_to_byte_offset(content, range.end);
+            content.replace_range(start..end, &change.text);
+        } else {
+            // this is a full update. The client has sent the entire document content.
+            *content = change.text;
+        }
+    }
+}
+
+/// A document that is not a template but that templates depend on.
+enum Source {
+    /// The state schema of a template, by the path of the template.
+    State(PathBuf),
+    Rust(PathBuf),
+}
+
+impl Source {
+    fn of(uri: &Url) -> Option<Self> {
+        let path = file_path(uri);
+        if let Some(template) = StateSchema::template_for(&path) {
+            return Some(Source::State(template));
+        }
+
+        is_rust_file(&path).then_some(Source::Rust(path))
+    }
+}
+
+/// The path a file is known by in the database. Files that don't live on disk are known by the
+/// path of their uri.
+fn file_path(uri: &Url) -> PathBuf {
+    uri.to_file_path()
+        .unwrap_or_else(|_| Path::new(uri.path()).to_path_buf())
+}
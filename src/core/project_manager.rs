@@ -1,19 +1,24 @@
+use std::path::Path;
 use std::sync::Arc;
 
 use aml_config::Config;
-use aml_semantic::global_scope::GlobalScope;
+use aml_core::Location;
+use aml_core::codes::{self, Code};
+use aml_core::workspace::get_root_template;
+use aml_semantic::{
+    AnalyzerOptions, DiagnosticSeverity, FileDiagnostics, LintLevels, SemanticDiagnostic, lints,
+};
 use tokio::sync::RwLock;
 use tower_lsp::lsp_types::*;
 
 use crate::core::document_manager::DocumentManager;
-use crate::core::template_service::TemplateService;
+use crate::core::template_service::{TemplateDiscoveryError, TemplateService};
 
 #[derive(Debug)]
 pub struct ProjectManager {
     pub config: Arc<RwLock<Config>>,
     pub template_service: Arc<RwLock<TemplateService>>,
     pub root_uri: Arc<RwLock<Option<Url>>>,
-    pub global_scope: Arc<RwLock<GlobalScope>>,
     pub document_manager: Arc<RwLock<DocumentManager>>,
 }
 
@@ -23,7 +28,6 @@ impl ProjectManager {
             config: Default::default(),
             root_uri: Default::default(),
             template_service: Arc::new(RwLock::new(TemplateService::new())),
-            global_scope: Default::default(),
             document_manager: Default::default(),
         }
     }
@@ -40,83 +44,124 @@ impl ProjectManager {
                 .and_then(|folders| folders.first().map(|folder| folder.uri.clone())),
         };
 
+        // without a root template, the templates opened in the editor are still analyzed
+        let _ = self.load(root_uri).await;
+    }
+
+    /// Loads the configuration, the Rust sources and every template of the project at `root_uri`.
+    pub async fn load(&self, root_uri: Option<Url>) -> Result<(), TemplateDiscoveryError> {
         let root_uri_path = root_uri.as_ref().and_then(|uri| uri.to_file_path().ok());
-        let config = aml_config::load_config(root_uri_path);
+        let config = aml_config::load_config(root_uri_path.clone());
+        let mut document_manager = self.document_manager.write().await;
+        document_manager.set_analyzer_options(analyzer_options(&config));
+        if let Some(root_dir) = root_uri_path {
+            document_manager.set_templates_dir(root_dir.join(&config.templates_dir));
+            document_manager.set_root_template(get_root_template(&root_dir, &config));
+            document_manager.set_config_diagnostics(config_diagnostics(&root_dir, &config));
+            document_manager.load_rust_sources(&root_dir);
+        }
+        drop(document_manager);
+
         *self.root_uri.write().await = root_uri.clone();
         *self.config.write().await = config;
 
-        self.initialize_workspace().await;
-        self.preload_workspace_templates().await;
+        let discovered = self.initialize_workspace().await;
+        let preloaded = self.preload_workspace_templates().await;
+        discovered.and(preloaded)
     }
 
-    pub async fn did_open(&self, params: DidOpenTextDocumentParams) {
-        let mut template_service = self.template_service.write().await;
-        let mut global_scope = self.global_scope.write().await;
-        self.document_manager
-            .write()
-            .await
-            .did_open(params, &mut global_scope, template_service.templates_mut())
-            .await;
+    /// Returns the other files whose diagnostics changed as a result of opening this one.
+    pub async fn did_open(&self, params: DidOpenTextDocumentParams) -> Vec<Url> {
+        self.document_manager.write().await.did_open(params).await
     }
 
-    pub async fn did_change(&self, params: DidChangeTextDocumentParams) {
-        let mut global_scope = self.global_scope.write().await;
-        self.document_manager
-            .write()
-            .await
-            .did_change(params, &mut global_scope)
-            .await;
+    /// Returns the other files whose diagnostics changed as a result of this change.
+    pub async fn did_change(&self, params: DidChangeTextDocumentParams) -> Vec<Url> {
+        self.document_manager.write().await.did_change(params).await
     }
 
-    pub async fn did_close(&self, params: DidCloseTextDocumentParams) {
-        self.document_manager.write().await.did_close(params).await;
+    /// Returns the other files whose diagnostics changed as a result of closing this one.
+    pub async fn did_close(&self, params: DidCloseTextDocumentParams) -> Vec<Url> {
+        self.document_manager.write().await.did_close(params).await
     }
 
     pub async fn goto_definition(
         &self,
         params: GotoDefinitionParams,
     ) -> tower_lsp::jsonrpc::Result<Option<GotoDefinitionResponse>> {
-        let document_manager = self.document_manager.write().await;
-        let global_scope = self.global_scope.read().await;
-
-        document_manager
-            .goto_definition(params, &global_scope)
-            .await
+        let document_manager = self.document_manager.read().await;
+        document_manager.goto_definition(params).await
     }
 
     pub async fn get_document_manager(&self) -> Arc<RwLock<DocumentManager>> {
         self.document_manager.clone()
     }
 
-    // TODO(wiru): what happens if we can't find the root template?
-    async fn initialize_workspace(&self) {
+    async fn initialize_workspace(&self) -> Result<(), TemplateDiscoveryError> {
         let config = self.config.read().await;
         let root_uri = self.root_uri.read().await;
         let mut template_service = self.template_service.write().await;
+        let document_manager = self.document_manager.read().await;
 
-        let Some(Ok(root_dir)) = root_uri.as_ref().map(|uri| uri.to_file_path()) else { return };
-
-        let mut global_scope = self.global_scope.write().await;
-        let mut document_manager = self.document_manager.write().await;
+        let Some(Ok(root_dir)) = root_uri.as_ref().map(|uri| uri.to_file_path()) else {
+            return Ok(());
+        };
 
-        let _ = template_service.discover_templates(
-            &root_dir,
-            &config,
-            &mut global_scope,
-            &mut document_manager,
-        );
+        let components = document_manager.components();
+        template_service.discover_templates(&root_dir, &config, components)
     }
 
     /// Preloads and analyzes all discovered templates to provide workspace-wide diagnostics.
     /// This runs after workspace initialization to ensure all templates are processed
     /// and available for cross-file analysis and diagnostics.
-    async fn preload_workspace_templates(&self) {
+    async fn preload_workspace_templates(&self) -> Result<(), TemplateDiscoveryError> {
         let template_service = self.template_service.read().await;
-        let mut global_scope = self.global_scope.write().await;
         let mut document_manager = self.document_manager.write().await;
 
-        let _ = template_service
-            .preload_templates(&mut global_scope, &mut document_manager)
-            .await;
+        template_service
+            .preload_templates(&mut document_manager)
+            .await
+    }
+}
+
+fn analyzer_options(config: &Config) -> AnalyzerOptions {
+    AnalyzerOptions {
+        lints: LintLevels::new(config.lints.levels.clone()),
+        min_contrast_ratio: config.min_contrast_ratio,
+        color_depth: config.color_depth,
+    }
+}
+
+/// Reports the settings of `aml_ls.toml` that are not valid, and the lints it names that do not
+/// exist.
+fn config_diagnostics(root_dir: &Path, config: &Config) -> FileDiagnostics {
+    let diagnostic = |code: &'static Code, span: &std::ops::Range<usize>, message: String| {
+        SemanticDiagnostic::new(
+            code,
+            Location::new(span.start, span.end),
+            message,
+            DiagnosticSeverity::Warning,
+        )
+    };
+
+    let mut diagnostics = config
+        .errors
+        .iter()
+        .map(|error| diagnostic(&codes::INVALID_CONFIG, &error.span, error.message.clone()))
+        .collect::<Vec<_>>();
+    let unknown_lints = config
+        .lints
+        .spans
+        .iter()
+        .filter(|(name, _)| lints::find(name).is_none())
+        .map(|(name, span)| {
+            diagnostic(&codes::UNKNOWN_LINT, span, format!("unknown lint '{name}'"))
+        });
+    diagnostics.extend(unknown_lints);
+    diagnostics.sort_by_key(|diagnostic| diagnostic.location);
+
+    match diagnostics.is_empty() {
+        true => FileDiagnostics::new(),
+        false => FileDiagnostics::from([(aml_config::config_path(root_dir), diagnostics)]),
     }
 }
@@ -1,10 +1,9 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use aml_config::Config;
-use aml_core::workspace::get_root_template;
-use aml_semantic::global_scope::{GlobalScope, GlobalSymbol};
-use aml_semantic::{SemanticAnalyzer, SymbolType};
+use aml_core::workspace::{get_root_template, search_for_templates};
+use aml_semantic::ComponentFiles;
 use aml_syntax::ast::*;
 use tower_lsp::lsp_types::*;
 
@@ -36,6 +35,10 @@ impl Templates {
         self.name_map.contains_key(name)
     }
 
+    pub fn has_template_by_path(&self, path: &Path) -> bool {
+        self.path_map.contains_key(path)
+    }
+
     pub fn paths(&self) -> impl Iterator<Item = &Template> {
         self.inner.iter()
     }
@@ -51,14 +54,14 @@ impl TemplateService {
         Self::default()
     }
 
-    /// Discovers all templates starting from the root template.
-    /// Returns the templates registry and any discovered global scope symbols.
+    /// Discovers all templates starting from the root template, following component references.
+    /// Components are looked up in `components`, which knows about the ones registered in Rust.
+    /// The templates nothing references are discovered as well, so they can be reported unused.
     pub fn discover_templates(
         &mut self,
         root_dir: &PathBuf,
         config: &Config,
-        global_scope: &mut GlobalScope,
-        document_manager: &mut DocumentManager,
+        components: &ComponentFiles,
     ) -> Result<(), TemplateDiscoveryError> {
         let root_template_path = get_root_template(root_dir, config)
             .ok_or(TemplateDiscoveryError::RootTemplateNotFound)?;
@@ -71,22 +74,35 @@ impl TemplateService {
             .register("index".into(), root_template_path.clone(), false);
 
         ast.accept(&mut TemplateCollector {
-            config,
             content: &content,
-            root_dir,
+            components,
             templates: &mut self.templates,
-            file_path: &root_template_path,
-            global_scope,
-            document_manager,
         });
 
+        let mut unreferenced = search_for_templates(root_dir.join(&config.templates_dir));
+        unreferenced.extend(
+            components
+                .registered
+                .values()
+                .filter(|path| path.is_file())
+                .cloned(),
+        );
+        unreferenced.sort();
+        for path in unreferenced {
+            if self.templates.has_template_by_path(&path) {
+                continue;
+            }
+
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else { continue };
+            self.templates.register(name.into(), path.clone(), false);
+        }
+
         Ok(())
     }
 
     /// Preloads all discovered templates for workspace-wide analysis.
     pub async fn preload_templates(
         &self,
-        global_scope: &mut GlobalScope,
         document_manager: &mut DocumentManager,
     ) -> Result<(), TemplateDiscoveryError> {
         for template in self.templates.paths() {
@@ -96,17 +112,13 @@ impl TemplateService {
             let uri = Url::from_file_path(&template.path)
                 .map_err(|_| TemplateDiscoveryError::InvalidPath(template.path.clone()))?;
 
-            let ast = parse_content(&content);
-            document_manager
-                .add_or_update_file(global_scope, uri, ast, content, 0)
-                .await;
+            document_manager.add_or_update_file(uri, content, 0).await;
         }
 
-        Ok(())
-    }
+        // templates using a component may have been analyzed before the component itself
+        document_manager.refresh().await;
 
-    pub fn templates_mut(&mut self) -> &mut Templates {
-        &mut self.templates
+        Ok(())
     }
 }
 
@@ -121,60 +133,38 @@ pub enum TemplateDiscoveryError {
 }
 
 /// Discovers and registers templates during workspace initialization by walking the AST of
-/// templates, collecting:
-///
-/// - Global variable declarations (registered in `GlobalScope`)
-/// - Component references (recursively processes referenced template files)
+/// templates and recursively processing the template files of the components they reference.
+/// Globals are collected by the analysis once the templates are loaded.
 struct TemplateCollector<'src> {
     content: &'src str,
-    config: &'src Config,
-    root_dir: &'src PathBuf,
-    file_path: &'src PathBuf,
+    components: &'src ComponentFiles,
     templates: &'src mut Templates,
-    global_scope: &'src mut GlobalScope,
-    document_manager: &'src mut DocumentManager,
 }
 
 impl<'src> AstVisitor<'src> for TemplateCollector<'src> {
-    fn visit_globals(&mut self, decl: &Declaration, _: &AstNode) {
-        let name = decl.name.text(self.content).into();
-        let mut analyzer = SemanticAnalyzer::new(self.content, self.global_scope);
-        let symbol_type = SymbolType::Variable(analyzer.analyze_expression(&decl.value));
-
-        self.global_scope.declare_global(GlobalSymbol {
-            name,
-            symbol_type,
-            location: decl.location,
-            definition: self.file_path.clone(),
-        });
-    }
-
     fn visit_component(&mut self, component: &Component, _: &AstNode) {
         let name = component.name.text(self.content);
-        let path = PathBuf::from(name).with_extension("aml");
-        let file_path = self.root_dir.join(&self.config.templates_dir).join(&path);
+        let Some(file_path) = self.components.file(name) else { return };
 
-        // Prevent infinite recursion by checking if template is already registered.
-        // TODO(wiru): this has to become a diagnostic of cyclic dependency
+        // Prevent infinite recursion by checking if template is already registered. Cycles between
+        // components are reported by the analyzer once every template is loaded.
         if self.templates.has_template_by_name(name) {
             return;
         }
 
+        // missing templates are reported by the analyzer, registering them would make preloading
+        // fail on the first one
+        let Ok(content) = std::fs::read_to_string(&file_path) else { return };
         self.templates
             .register(name.into(), file_path.clone(), false);
-        let Ok(content) = std::fs::read_to_string(&file_path) else { return };
         let tokens = aml_token::Lexer::new(&content).collect();
         let tokens = aml_token::Tokens::new(tokens, content.len());
         let ast = aml_syntax::Parser::new(tokens).parse();
 
         ast.accept(&mut TemplateCollector {
             content: &content,
-            config: self.config,
-            file_path: &file_path,
-            root_dir: self.root_dir,
+            components: self.components,
             templates: self.templates,
-            global_scope: self.global_scope,
-            document_manager: self.document_manager,
         });
     }
 
@@ -196,4 +186,3 @@ impl<'src> AstVisitor<'src> for TemplateCollector<'src> {
         }
     }
 }
-
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use tower_lsp::lsp_types::*;
+
+use crate::core::document_manager::DocumentManager;
+
+/// Offers the fixes the analysis suggests for the diagnostics in a range as quick fixes.
+#[derive(Debug)]
+pub struct CodeActionProvider;
+
+impl CodeActionProvider {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn code_actions(
+        &self,
+        document_manager: &DocumentManager,
+        params: CodeActionParams,
+    ) -> CodeActionResponse {
+        let uri = params.text_document.uri;
+        let files = document_manager.files().read().await;
+        let Some(file_info) = files.get(&uri) else { return Vec::new() };
+
+        let content = &file_info.content;
+        let start = DocumentManager::position_to_byte_offset(content, params.range.start);
+        let end = DocumentManager::position_to_byte_offset(content, params.range.end);
+        let range = |location: aml_core::Location| Range {
+            start: DocumentManager::byte_offset_to_position(content, location.start_byte),
+            end: DocumentManager::byte_offset_to_position(content, location.end_byte),
+        };
+
+        file_info
+            .semantic_info
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| {
+                diagnostic.location.start_byte <= end && start <= diagnostic.location.end_byte
+            })
+            .flat_map(|diagnostic| diagnostic.fixes.iter())
+            .map(|fix| {
+                let edit = TextEdit {
+                    range: range(fix.location),
+                    new_text: fix.replacement.clone(),
+                };
+
+                CodeActionOrCommand::CodeAction(CodeAction {
+                    title: fix.title.clone(),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                        ..WorkspaceEdit::default()
+                    }),
+                    is_preferred: Some(true),
+                    ..CodeAction::default()
+                })
+            })
+            .collect()
+    }
+}
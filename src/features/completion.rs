@@ -0,0 +1,37 @@
+use tower_lsp::lsp_types::*;
+
+use crate::core::document_manager::DocumentManager;
+
+/// Completes the fields of the template state in expressions.
+#[derive(Debug)]
+pub struct CompletionProvider;
+
+impl CompletionProvider {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn completions(
+        &self,
+        document_manager: &DocumentManager,
+        params: CompletionParams,
+    ) -> Vec<CompletionItem> {
+        let uri = params.text_document_position.text_document.uri;
+        let files = document_manager.files().read().await;
+        let Some(file_info) = files.get(&uri) else { return Vec::new() };
+
+        file_info
+            .semantic_info
+            .symbol_table
+            .state_fields()
+            .into_iter()
+            .map(|field| CompletionItem {
+                label: field.name.clone(),
+                kind: Some(CompletionItemKind::FIELD),
+                detail: Some(field.symbol_type.value_type().to_string()),
+                documentation: Some(Documentation::String("Field of the template state".into())),
+                ..CompletionItem::default()
+            })
+            .collect()
+    }
+}
@@ -1,15 +1,39 @@
-use aml_core::Location;
-use aml_semantic::{DiagnosticSeverity as SemanticDiagnosticSeverity, SemanticDiagnostic};
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+use aml_semantic::{
+    DiagnosticSeverity as SemanticDiagnosticSeverity, DiagnosticTag as SemanticDiagnosticTag,
+    RelatedInformation, SemanticDiagnostic,
+};
 use tower_lsp::lsp_types::*;
 
 use crate::core::document_manager::DocumentManager;
 
 #[derive(Debug)]
-pub struct DiagnosticProvider;
+pub struct DiagnosticProvider {
+    /// Where the explanations of the diagnostic codes are written, `None` if they could not be.
+    explanations: Option<PathBuf>,
+}
 
 impl DiagnosticProvider {
     pub fn new() -> Self {
-        Self
+        // each server writes its own copy, so they always match its version and its user
+        let dir = std::env::temp_dir()
+            .join(format!("aml-ls-{}", std::process::id()))
+            .join("diagnostics");
+        let codes = aml_core::codes::CODES.iter().map(|code| code.code);
+        let explanations = match aml_docs::write_explanations(&dir, codes) {
+            Ok(()) => Some(dir),
+            Err(error) => {
+                eprintln!(
+                    "Could not write the explanations of the diagnostics to {}: {error}",
+                    dir.display()
+                );
+                None
+            }
+        };
+
+        Self { explanations }
     }
 
     pub async fn get_diagnostics(
@@ -19,42 +43,147 @@ impl DiagnosticProvider {
     ) -> Vec<Diagnostic> {
         let files = document_manager.files().read().await;
         let Some(file_info) = files.get(uri) else {
-            return Vec::new();
+            return self.get_source_diagnostics(document_manager, uri);
         };
 
         file_info
             .semantic_info
             .diagnostics
             .iter()
+            .chain(document_manager.workspace_diagnostics(uri))
             .map(|diag| {
-                self.convert_semantic_diagnostic(diag, document_manager, &file_info.content)
+                self.convert_semantic_diagnostic(diag, uri, &file_info.content, document_manager)
             })
             .collect()
     }
 
+    /// The diagnostics of a file that is not a template, e.g. a Rust file, which is read from disk
+    /// unless it is open or a state schema.
+    fn get_source_diagnostics(
+        &self,
+        document_manager: &DocumentManager,
+        uri: &Url,
+    ) -> Vec<Diagnostic> {
+        let diagnostics = document_manager.workspace_diagnostics(uri);
+        if diagnostics.is_empty() {
+            return Vec::new();
+        }
+
+        let Ok(path) = uri.to_file_path() else { return Vec::new() };
+        let content = match document_manager.source(uri) {
+            Some(content) => Cow::Borrowed(content),
+            None => match document_manager.text(&path) {
+                Some(content) => Cow::Owned(content.to_string()),
+                None => {
+                    let Ok(content) = std::fs::read_to_string(path) else { return Vec::new() };
+                    Cow::Owned(content)
+                }
+            },
+        };
+
+        diagnostics
+            .iter()
+            .map(|diag| self.convert_semantic_diagnostic(diag, uri, &content, document_manager))
+            .collect()
+    }
+
     fn convert_semantic_diagnostic(
         &self,
         semantic_diag: &SemanticDiagnostic,
-        _document_manager: &DocumentManager,
+        uri: &Url,
         content: &str,
+        document_manager: &DocumentManager,
     ) -> Diagnostic {
         let range = self.location_to_range(semantic_diag.location, content);
         let severity = self.convert_severity(&semantic_diag.severity);
+        let related_information =
+            self.convert_related_information(semantic_diag, uri, content, document_manager);
+        let tags = self.convert_tags(&semantic_diag.tags);
 
         Diagnostic {
             range,
             severity: Some(severity),
-            code: None,
-            code_description: None,
+            code: Some(NumberOrString::String(semantic_diag.code.code.into())),
+            code_description: self.code_description(semantic_diag),
             source: Some("aml-ls".to_string()),
             message: semantic_diag.message.clone(),
-            related_information: None,
-            tags: None,
+            related_information,
+            tags,
             data: None,
         }
     }
 
-    fn location_to_range(&self, location: Location, content: &str) -> Range {
+    /// Links the code of a diagnostic to its explanation, when the explanations were written.
+    fn code_description(&self, semantic_diag: &SemanticDiagnostic) -> Option<CodeDescription> {
+        let path = self
+            .explanations
+            .as_ref()?
+            .join(format!("{}.md", semantic_diag.code.code));
+        let href = Url::from_file_path(path).ok()?;
+        Some(CodeDescription { href })
+    }
+
+    fn convert_related_information(
+        &self,
+        semantic_diag: &SemanticDiagnostic,
+        uri: &Url,
+        content: &str,
+        document_manager: &DocumentManager,
+    ) -> Option<Vec<DiagnosticRelatedInformation>> {
+        if semantic_diag.related.is_empty() {
+            return None;
+        }
+
+        let related = semantic_diag
+            .related
+            .iter()
+            .filter_map(|related| {
+                let Some(path) = &related.file else {
+                    return Some(self.convert_related(related, uri.clone(), content));
+                };
+
+                // the content of the related file turns byte offsets into positions
+                let related_uri = Url::from_file_path(path).ok()?;
+                let related_content = document_manager.text(path)?;
+
+                Some(self.convert_related(related, related_uri, &related_content))
+            })
+            .collect();
+
+        Some(related)
+    }
+
+    fn convert_related(
+        &self,
+        related: &RelatedInformation,
+        uri: Url,
+        content: &str,
+    ) -> DiagnosticRelatedInformation {
+        DiagnosticRelatedInformation {
+            location: Location {
+                uri,
+                range: self.location_to_range(related.location, content),
+            },
+            message: related.message.clone(),
+        }
+    }
+
+    fn convert_tags(&self, tags: &[SemanticDiagnosticTag]) -> Option<Vec<DiagnosticTag>> {
+        if tags.is_empty() {
+            return None;
+        }
+
+        let tags = tags
+            .iter()
+            .map(|tag| match tag {
+                SemanticDiagnosticTag::Unnecessary => DiagnosticTag::UNNECESSARY,
+            })
+            .collect();
+
+        Some(tags)
+    }
+
+    fn location_to_range(&self, location: aml_core::Location, content: &str) -> Range {
         let start_pos = DocumentManager::byte_offset_to_position(content, location.start_byte);
         let end_pos = DocumentManager::byte_offset_to_position(content, location.end_byte);
 
@@ -69,6 +198,7 @@ impl DiagnosticProvider {
             SemanticDiagnosticSeverity::Error => DiagnosticSeverity::ERROR,
             SemanticDiagnosticSeverity::Warning => DiagnosticSeverity::WARNING,
             SemanticDiagnosticSeverity::Info => DiagnosticSeverity::INFORMATION,
+            SemanticDiagnosticSeverity::Hint => DiagnosticSeverity::HINT,
         }
     }
 }
@@ -1,10 +1,15 @@
+use std::path::Path;
+
+use aml_semantic::functions::lookup_function;
+use aml_semantic::global_scope::GlobalScope;
+use aml_semantic::{ReferenceTarget, SymbolType};
 use aml_syntax::ast::*;
 use aml_syntax::{NodeFinder, NodeFinderResult};
 use aml_token::Container;
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 
-use crate::core::document_manager::DocumentManager;
+use crate::core::document_manager::{DocumentManager, FileInfo};
 
 #[derive(Debug)]
 pub struct HoverProvider {
@@ -31,16 +36,54 @@ impl HoverProvider {
 
         let byte_offset = DocumentManager::position_to_byte_offset(&file_info.content, position);
 
+        if let Some(computed) = file_info.semantic_info.computed_value_at(byte_offset) {
+            let contents = HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("Computed value: `{}`", computed.value),
+            });
+
+            let range = Some(Range {
+                start: DocumentManager::byte_offset_to_position(
+                    &file_info.content,
+                    computed.location.start_byte,
+                ),
+                end: DocumentManager::byte_offset_to_position(
+                    &file_info.content,
+                    computed.location.end_byte,
+                ),
+            });
+
+            return Ok(Some(Hover { contents, range }));
+        }
+
         let mut finder = NodeFinder {
             byte_offset,
             result: None,
         };
 
         file_info.ast.accept(&mut finder);
-        let Some(NodeFinderResult::Node(node)) = finder.result else { return Ok(None) };
-
-        let location = node.location();
-        let value = self.get_hover_content(node);
+        let (location, value) = match finder.result {
+            Some(NodeFinderResult::Node(node)) => {
+                let value = match node {
+                    AstNode::Identifier(location) => {
+                        identifier_type(file_info, ctx.document_manager.global_scope(), *location)
+                            .unwrap_or_else(|| self.get_hover_content(node))
+                    }
+                    node => self.get_hover_content(node),
+                };
+                (node.location(), value)
+            }
+            // names in expressions are variables, or the functions they call
+            Some(NodeFinderResult::Expr(Expr::Ident(location))) => {
+                let name = &file_info.content[location.to_range()];
+                let value =
+                    identifier_type(file_info, ctx.document_manager.global_scope(), *location)
+                        .or_else(|| function_signature(name));
+                let Some(value) = value else { return Ok(None) };
+                (*location, value)
+            }
+            _ => return Ok(None),
+        };
 
         let contents = HoverContents::Markup(MarkupContent {
             kind: MarkupKind::Markdown,
@@ -48,7 +91,10 @@ impl HoverProvider {
         });
 
         let range = Some(Range {
-            start: DocumentManager::byte_offset_to_position(&file_info.content, location.start_byte),
+            start: DocumentManager::byte_offset_to_position(
+                &file_info.content,
+                location.start_byte,
+            ),
             end: DocumentManager::byte_offset_to_position(&file_info.content, location.end_byte),
         });
 
@@ -85,3 +131,74 @@ impl HoverProvider {
         }
     }
 }
+
+/// Shows the signature and the description of a built-in function.
+fn function_signature(name: &str) -> Option<String> {
+    let function = lookup_function(name)?;
+    Some(format!(
+        "```\n{}\n```\n\n{}",
+        function.signature(),
+        function.description
+    ))
+}
+
+/// Shows the type of a global along with the template declaring it.
+fn global_type(global_scope: &GlobalScope, name: &str, definition: &Path) -> Option<String> {
+    let global = global_scope
+        .declarations(name)
+        .iter()
+        .find(|global| global.definition == definition)?;
+    let value_type = global.symbol_type.value_type();
+
+    Some(format!(
+        "```\nglobal {name}: {value_type}\n```\n\nDeclared in `{}`",
+        definition.display()
+    ))
+}
+
+/// Shows the type of the symbol an identifier refers to, e.g. `name: string | null`. Identifiers
+/// that are not reads, like declared names, use the closest declaration.
+fn identifier_type(
+    file_info: &FileInfo,
+    global_scope: &GlobalScope,
+    location: aml_core::Location,
+) -> Option<String> {
+    let name = &file_info.content[location.to_range()];
+    let symbol_table = &file_info.semantic_info.symbol_table;
+    let symbol_type = match symbol_table.reference_at(location.start_byte) {
+        Some(reference) => match &reference.target {
+            ReferenceTarget::Global { definition } => {
+                return global_type(global_scope, name, definition);
+            }
+            _ => &symbol_table.resolve(reference)?.symbol_type,
+        },
+        None => {
+            &symbol_table
+                .find_references(name)
+                .into_iter()
+                .filter(|symbol| symbol.location.start_byte <= location.start_byte)
+                .max_by_key(|symbol| symbol.location.start_byte)?
+                .symbol_type
+        }
+    };
+
+    let value_type = symbol_type.value_type();
+    let SymbolType::State(_) = symbol_type else {
+        return Some(format!("```\n{name}: {value_type}\n```"));
+    };
+
+    let rust_type = file_info
+        .semantic_info
+        .state
+        .as_ref()
+        .and_then(|state| state.field(name)?.rust_type.as_ref());
+
+    match rust_type {
+        Some(rust_type) => Some(format!(
+            "```\n{name}: {value_type}\n```\n\nField of the template state\n\n```rust\n{name}: {rust_type}\n```"
+        )),
+        None => Some(format!(
+            "```\n{name}: {value_type}\n```\n\nField of the template state"
+        )),
+    }
+}
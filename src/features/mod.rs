@@ -1,2 +1,4 @@
+pub mod code_actions;
+pub mod completion;
 pub mod diagnostics;
 pub mod hover;
@@ -1,7 +1,10 @@
 mod core;
 mod features;
+mod report;
 mod server;
 
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand};
 
 #[derive(Parser, Debug)]
@@ -18,16 +21,52 @@ enum Commands {
         /// The path to create the amls.toml file in.
         path: Option<String>,
     },
+    /// Lists the templates and the globals nothing in the project uses
+    Unused {
+        /// The root of the project, the current working directory by default.
+        path: Option<PathBuf>,
+    },
+    /// Explains a diagnostic code, e.g. `aml_ls explain AML0001`
+    Explain {
+        /// The code or the name of the diagnostic.
+        code: String,
+    },
 }
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
 
-    if let Some(Commands::Init { path }) = &cli.command {
-        println!("Initialising in {}", path.as_deref().unwrap_or("."));
-        return;
-    }
+    match &cli.command {
+        Some(Commands::Init { path }) => {
+            println!("Initialising in {}", path.as_deref().unwrap_or("."));
+        }
+        Some(Commands::Unused { path }) => {
+            let path = path.clone().unwrap_or_else(|| PathBuf::from("."));
+            let Ok(root_dir) = path.canonicalize() else {
+                eprintln!("No such directory: {}", path.display());
+                std::process::exit(2);
+            };
+
+            match report::report_unused(&root_dir).await {
+                Ok(true) => std::process::exit(1),
+                Ok(false) => {}
+                Err(error) => {
+                    eprintln!("{error}");
+                    std::process::exit(2);
+                }
+            }
+        }
+        Some(Commands::Explain { code }) => {
+            let Some(explanation) =
+                aml_core::codes::find(code).and_then(|code| aml_docs::explanation(code.code))
+            else {
+                eprintln!("Unknown diagnostic code: {code}");
+                std::process::exit(2);
+            };
 
-    server::start().await;
+            print!("{explanation}");
+        }
+        None => server::start().await,
+    }
 }
@@ -0,0 +1,64 @@
+use std::path::Path;
+
+use aml_semantic::DiagnosticSeverity;
+use aml_semantic::lints::{UNUSED_GLOBAL, UNUSED_TEMPLATE};
+use tower_lsp::lsp_types::Url;
+
+use crate::core::document_manager::DocumentManager;
+use crate::core::project_manager::ProjectManager;
+use crate::core::template_service::TemplateDiscoveryError;
+
+/// Prints the templates and globals nothing in the project at `root_dir` uses, one per line as
+/// `path:line:column: severity: message`. Returns whether anything was reported, or why the
+/// templates of the project could not be found.
+pub async fn report_unused(root_dir: &Path) -> Result<bool, TemplateDiscoveryError> {
+    let project_manager = ProjectManager::new();
+    project_manager
+        .load(Url::from_directory_path(root_dir).ok())
+        .await?;
+
+    let document_manager = project_manager.get_document_manager().await;
+    let document_manager = document_manager.read().await;
+    let files = document_manager.files().read().await;
+
+    let mut uris = document_manager
+        .workspace_diagnostic_uris()
+        .collect::<Vec<_>>();
+    uris.sort();
+
+    let lints = [UNUSED_TEMPLATE.name(), UNUSED_GLOBAL.name()];
+    let mut reported = false;
+    for uri in uris {
+        let Some(file) = files.get(uri) else { continue };
+        let Ok(path) = uri.to_file_path() else { continue };
+        let path = path.strip_prefix(root_dir).unwrap_or(&path);
+
+        let diagnostics = document_manager
+            .workspace_diagnostics(uri)
+            .iter()
+            .filter(|diagnostic| diagnostic.lint.is_some_and(|lint| lints.contains(&lint)));
+        for diagnostic in diagnostics {
+            let position = DocumentManager::byte_offset_to_position(
+                &file.content,
+                diagnostic.location.start_byte,
+            );
+            let severity = match diagnostic.severity {
+                DiagnosticSeverity::Error => "error",
+                DiagnosticSeverity::Warning => "warning",
+                DiagnosticSeverity::Info => "info",
+                DiagnosticSeverity::Hint => "hint",
+            };
+            println!(
+                "{}:{}:{}: {severity}: {} [{}]",
+                path.display(),
+                position.line + 1,
+                position.character + 1,
+                diagnostic.message,
+                diagnostic.lint.unwrap_or_default(),
+            );
+            reported = true;
+        }
+    }
+
+    Ok(reported)
+}
@@ -5,6 +5,8 @@ use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
 
 use crate::core::project_manager::ProjectManager;
+use crate::features::code_actions::CodeActionProvider;
+use crate::features::completion::CompletionProvider;
 use crate::features::diagnostics::DiagnosticProvider;
 use crate::features::hover::{HoverContext, HoverProvider};
 
@@ -14,6 +16,8 @@ pub struct Backend {
     project_manager: ProjectManager,
     hover_provider: HoverProvider,
     diagnostic_provider: DiagnosticProvider,
+    code_action_provider: CodeActionProvider,
+    completion_provider: CompletionProvider,
 }
 
 #[tower_lsp::async_trait]
@@ -27,7 +31,18 @@ impl LanguageServer for Backend {
         })
     }
 
-    async fn initialized(&self, _: InitializedParams) {}
+    /// Publishes the diagnostics found by loading the whole workspace, such as unused templates,
+    /// which are otherwise only published once their file is opened.
+    async fn initialized(&self, _: InitializedParams) {
+        let document_manager = self.project_manager.get_document_manager().await;
+        let uris = document_manager
+            .read()
+            .await
+            .workspace_diagnostic_uris()
+            .cloned()
+            .collect::<Vec<_>>();
+        self.publish_all_diagnostics(&uris).await;
+    }
 
     async fn shutdown(&self) -> Result<()> {
         Ok(())
@@ -35,20 +50,23 @@ impl LanguageServer for Backend {
 
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
         let uri = params.text_document.uri.clone();
-        self.project_manager.did_open(params).await;
+        let dependents = self.project_manager.did_open(params).await;
         self.publish_diagnostics(&uri).await;
+        self.publish_all_diagnostics(&dependents).await;
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         let uri = params.text_document.uri.clone();
-        self.project_manager.did_change(params).await;
+        let dependents = self.project_manager.did_change(params).await;
         self.publish_diagnostics(&uri).await;
+        self.publish_all_diagnostics(&dependents).await;
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
         let uri = params.text_document.uri.clone();
-        self.project_manager.did_close(params).await;
+        let dependents = self.project_manager.did_close(params).await;
         self.client.publish_diagnostics(uri, Vec::new(), None).await;
+        self.publish_all_diagnostics(&dependents).await;
     }
 
     async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
@@ -63,13 +81,33 @@ impl LanguageServer for Backend {
             .await
     }
 
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let document_manager = self.project_manager.get_document_manager().await;
+        let document_manager_guard = document_manager.read().await;
+
+        let actions = self
+            .code_action_provider
+            .code_actions(&document_manager_guard, params)
+            .await;
+        Ok(Some(actions))
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let document_manager = self.project_manager.get_document_manager().await;
+        let document_manager_guard = document_manager.read().await;
+
+        let items = self
+            .completion_provider
+            .completions(&document_manager_guard, params)
+            .await;
+        Ok(Some(CompletionResponse::Array(items)))
+    }
+
     async fn goto_definition(
         &self,
         params: GotoDefinitionParams,
     ) -> Result<Option<GotoDefinitionResponse>> {
-        let result = self.project_manager.goto_definition(params).await;
-
-        result
+        self.project_manager.goto_definition(params).await
     }
 }
 
@@ -86,6 +124,12 @@ impl Backend {
             .publish_diagnostics(uri.clone(), diagnostics, None)
             .await;
     }
+
+    async fn publish_all_diagnostics(&self, uris: &[Url]) {
+        for uri in uris {
+            self.publish_diagnostics(uri).await;
+        }
+    }
 }
 
 pub async fn start() {
@@ -97,6 +141,8 @@ pub async fn start() {
         project_manager: ProjectManager::new(),
         hover_provider: HoverProvider::new(),
         diagnostic_provider: DiagnosticProvider::new(),
+        code_action_provider: CodeActionProvider::new(),
+        completion_provider: CompletionProvider::new(),
     });
 
     Server::new(stdin, stdout, socket).serve(service).await;